@@ -1,10 +1,185 @@
-from typing import List, Dict, Tuple, Optional
+from typing import Any, Awaitable, Callable, Generator, Iterator, List, Dict, Tuple, Optional
 
 
-def start_engine() -> None: ...
+class EngineError(RuntimeError): ...
 
 
-def stop_engine() -> None: ...
+class EngineNotStartedError(EngineError): ...
+
+
+class EngineTransportError(EngineError): ...
+
+
+class EngineCommandRejected(EngineError): ...
+
+
+class EngineTimeoutError(EngineError): ...
+
+
+class EngineCommandCancelled(EngineError): ...
+
+
+class EngineProtocolError(EngineError): ...
+
+
+class EngineMemoryError(EngineError): ...
+
+
+PROTOCOL_VERSION: int
+CAP_RESPONSE_EVENTS: int
+CAP_TRANSFORM_UPDATES: int
+CAP_SLAB_GENERATIONS: int
+CAP_ASSET_MAGIC: int
+
+
+class CancellationToken:
+    def __init__(self) -> None: ...
+    def cancel(self) -> None: ...
+    @property
+    def cancelled(self) -> bool: ...
+
+
+class CommandScope:
+    def __enter__(self) -> "CommandScope": ...
+    def __exit__(self, exc_type, exc_value, traceback) -> bool: ...
+
+
+def set_command_timeout(seconds: Optional[float] = None) -> None: ...
+
+
+def get_command_timeout() -> Optional[float]: ...
+
+
+def set_max_in_flight(max_in_flight: int) -> None: ...
+
+
+class MeshQueuePolicy:
+    Block: "MeshQueuePolicy"
+    DropOldest: "MeshQueuePolicy"
+    CoalesceByUuid: "MeshQueuePolicy"
+
+
+class MeshQueueStats:
+    capacity: int
+    policy: MeshQueuePolicy
+    queued: int
+    received: int
+    dropped: int
+    coalesced: int
+    blocked: int
+
+
+def set_mesh_queue(
+    capacity: int = 64,
+    policy: MeshQueuePolicy = MeshQueuePolicy.Block,
+) -> None: ...
+
+
+def mesh_queue_stats() -> MeshQueueStats: ...
+
+
+class EngineState:
+    Starting: "EngineState"
+    Ready: "EngineState"
+    Crashed: "EngineState"
+    Stopped: "EngineState"
+
+
+def engine_state() -> EngineState: ...
+
+
+def set_engine_state_callback(
+    callback: Optional[Callable[[EngineState, Optional[str]], None]] = None,
+) -> None: ...
+
+
+def set_restart_policy(
+    max_restarts: int = 3,
+    initial_backoff: float = 0.5,
+    max_backoff: float = 30.0,
+) -> None: ...
+
+
+def command_scope(
+    timeout: Optional[float] = None,
+    cancel: Optional[CancellationToken] = None,
+) -> CommandScope: ...
+
+
+class EngineLaunchConfig:
+    args: List[str]
+    env: Dict[str, str]
+    cwd: Optional[str]
+    log_file: Optional[str]
+    log_file_max_bytes: int
+    log_file_backups: int
+    log_buffer_lines: int
+    echo: bool
+
+    def __init__(
+        self,
+        args: List[str] = ...,
+        env: Dict[str, str] = ...,
+        cwd: Optional[str] = None,
+        log_file: Optional[str] = None,
+        log_file_max_bytes: int = 10485760,
+        log_file_backups: int = 3,
+        log_buffer_lines: int = 2000,
+        echo: bool = False,
+    ) -> None: ...
+
+
+class SdkConfig:
+    service_prefix: str
+    node_name: Optional[str]
+    iceoryx2_config: Optional[str]
+    domain: Optional[str]
+
+    def __init__(
+        self,
+        service_prefix: str = "PivotEngine",
+        node_name: Optional[str] = None,
+        iceoryx2_config: Optional[str] = None,
+        domain: Optional[str] = None,
+    ) -> None: ...
+
+
+def set_sdk_config(config: SdkConfig) -> None: ...
+
+
+def get_sdk_config() -> SdkConfig: ...
+
+
+def start_engine(
+    timeout: Optional[float] = None,
+    config: Optional[EngineLaunchConfig] = None,
+) -> None: ...
+
+
+def connect_engine(timeout: Optional[float] = None) -> None: ...
+
+
+def owns_engine() -> Optional[bool]: ...
+
+
+def engine_logs(n: int = 100) -> List[str]: ...
+
+
+def engine_version() -> Optional[str]: ...
+
+
+def engine_capabilities() -> int: ...
+
+
+class StopOutcome:
+    NotRunning: "StopOutcome"
+    Detached: "StopOutcome"
+    Graceful: "StopOutcome"
+    Terminated: "StopOutcome"
+    Killed: "StopOutcome"
+
+
+def stop_engine(timeout: Optional[float] = None, drain: bool = True) -> StopOutcome: ...
 
 
 def standardize_synced_groups_command(
@@ -19,15 +194,37 @@ def set_surface_types_command(group_surface_map: Dict[bytes, int]) -> None: ...
 def drop_groups_command(uuids: List[bytes]) -> None: ...
 
 
-def get_surface_types_command() -> None: ...
+def get_surface_types_command() -> Dict[bytes, int]: ...
 
 
 def organize_objects_command() -> None: ...
 
 
+def export_all_asset_tbo_command(path: str, target_bytes: int) -> List[str]: ...
+
+
 def poll_mesh_sync() -> Optional["AssetSyncContext"]: ...
 
 
+def send_transforms(uuids: List[bytes], matrices: Any) -> None:
+    """Move objects without resending geometry; ``matrices`` holds a float32 4x4 per UUID."""
+    ...
+
+
+def poll_mesh_sync_all(max: Optional[int] = None) -> Optional["AssetSyncContext"]: ...
+
+
+def wait_mesh_sync(timeout: Optional[float] = None) -> Optional["AssetSyncContext"]: ...
+
+
+def subscribe_mesh_updates(
+    callback: Callable[["AssetSyncContext"], None],
+) -> "MeshSubscription": ...
+
+
+def mesh_updates(timeout: Optional[float] = None) -> "MeshUpdates": ...
+
+
 def prepare_standardize_groups(
     vert_counts: List[int],
     edge_counts: List[int],
@@ -53,9 +250,299 @@ def get_platform_id() -> str: ...
 def set_engine_dir(path: str) -> None: ...
 
 
+class AssetBuffer:
+    """Owner of a memoryview returned by AssetSyncContext.buffers() (``view.obj``).
+
+    New views are refused, and the views already handed out are released, once the
+    context is sent or its engine stops.
+    """
+    @property
+    def name(self) -> str: ...
+    @property
+    def readonly(self) -> bool: ...
+    @property
+    def valid(self) -> bool: ...
+    def __len__(self) -> int: ...
+
+
+class AssetArrays:
+    """Typed, zero-copy NumPy arrays over one asset (built with the ``numpy`` feature)."""
+    object_uuids: Any  # uint8[K, 32]
+    verts: Any  # float32[N, 3]
+    edges: Any  # uint32[E, 2]
+    loops: Any  # uint32[L]
+    loop_bases: Any  # uint32
+    object_loop_counts: Any  # uint32[K]
+    transforms: Any  # float32[K, 4, 4]
+    vert_counts: Any  # uint32[K]
+    edge_counts: Any  # uint32[K]
+    object_names: Any  # uint8[K, W], one NUL padded slot per object
+    embeddings: Any  # float32
+
+
+class AssetView:
+    """One asset of an AssetSyncContext; slices are memoryviews with the context's lifetime."""
+    @property
+    def index(self) -> int: ...
+    @property
+    def uuid(self) -> bytes: ...
+    @property
+    def surface_context(self) -> int: ...
+    @property
+    def group_name(self) -> str: ...
+    @property
+    def names(self) -> List[str]:
+        """Object names decoded from object_names (fixed-width, NUL padded UTF-8 slots)."""
+        ...
+    def set_object_names(self, names: List[str]) -> None: ...
+    @property
+    def verts(self) -> memoryview: ...
+    @property
+    def edges(self) -> memoryview: ...
+    @property
+    def loops(self) -> memoryview: ...
+    @property
+    def loop_bases(self) -> memoryview: ...
+    @property
+    def object_loop_counts(self) -> memoryview: ...
+    @property
+    def transforms(self) -> memoryview: ...
+    @property
+    def vert_counts(self) -> memoryview: ...
+    @property
+    def edge_counts(self) -> memoryview: ...
+    @property
+    def object_names(self) -> memoryview: ...
+    @property
+    def object_uuids(self) -> memoryview: ...
+    @property
+    def embeddings(self) -> memoryview: ...
+
+
 class AssetSyncContext:
+    @property
+    def readonly(self) -> bool: ...
+    @property
+    def transforms_only(self) -> bool:
+        """True if the update only moved objects; geometry slices are unchanged."""
+        ...
+    @property
+    def valid(self) -> bool: ...
     def uuids(self) -> memoryview: ...
     def surface_contexts(self) -> memoryview: ...
-    def buffers(self, i: int) -> Tuple[memoryview, memoryview, memoryview, memoryview, memoryview, memoryview, memoryview, memoryview, memoryview, memoryview]: ...
+    def buffers(self, i: int) -> Tuple[
+        memoryview, memoryview, memoryview, memoryview, memoryview, memoryview,
+        memoryview, memoryview, memoryview, memoryview, memoryview,
+    ]:
+        """(verts, edges, loops, loop_bases, object_loop_counts, transforms,
+        vert_counts, edge_counts, object_names, object_uuids, embeddings)"""
+        ...
+    def group_names(self) -> List[str]: ...
+    def object_names(self, i: int) -> List[str]: ...
+    def set_object_names(self, i: int, names: List[str]) -> None:
+        """Writes one name per object; each must fit its slot. Fails on read-only contexts."""
+        ...
+    def arrays(self, i: int) -> AssetArrays:
+        """Only available when built with the ``numpy`` feature."""
+        ...
     def size(self) -> int: ...
-    def finalize(self) -> None: ...
+    def __len__(self) -> int: ...
+    def __getitem__(self, i: int) -> AssetView: ...
+    def __iter__(self) -> Iterator[AssetView]: ...
+    def send(self) -> None:
+        """Raises EngineMemoryError while a view into the context is still exported,
+        e.g. by a numpy array. Read-only contexts cannot be sent; a failed send leaves
+        the context usable."""
+        ...
+
+
+class MeshBatchBuilder:
+    """Fills and sends an AssetSyncContext from per-asset arrays.
+
+    Arrays accept any buffer of the right element type (float32 / uint32, e.g. NumPy)
+    or a plain sequence of numbers.
+    """
+    def __init__(self, engine: Optional[Engine] = None) -> None: ...
+    def add_asset(
+        self,
+        group_name: str,
+        verts: Any,
+        edges: Any,
+        loops: Any,
+        loop_bases: Any,
+        object_loop_counts: Any,
+        vert_counts: Any,
+        edge_counts: Any,
+        transforms: Any,
+        object_names: List[str],
+        object_uuids: Optional[List[bytes]] = None,
+        surface_context: int = 0,
+        uuid: Optional[bytes] = None,
+    ) -> int: ...
+    def __len__(self) -> int: ...
+    def clear(self) -> None: ...
+    def build(self) -> AssetSyncContext: ...
+    def send(self) -> None: ...
+
+
+class MeshSubscription:
+    """Delivers mesh updates to a callback on a background thread until unsubscribed."""
+    @property
+    def active(self) -> bool: ...
+    def unsubscribe(self) -> None: ...
+    def __enter__(self) -> MeshSubscription: ...
+    def __exit__(self, *args: Any) -> None: ...
+
+
+class MeshUpdates:
+    """Mesh updates for ``for`` and ``async for``; ends after ``timeout`` idle seconds.
+    Raises EngineNotStartedError when no engine is connected."""
+    def __iter__(self) -> Iterator[AssetSyncContext]: ...
+    def __next__(self) -> AssetSyncContext: ...
+    def __aiter__(self) -> MeshUpdates: ...
+    def __anext__(self) -> Awaitable[AssetSyncContext]: ...
+
+
+class CommandHandle:
+    def done(self) -> bool: ...
+    def result(self, timeout: Optional[float] = None) -> Any: ...
+    def cancel(self) -> None: ...
+    def __await__(self) -> Generator[Any, None, Any]: ...
+
+
+def organize_objects_command_async() -> CommandHandle: ...
+
+
+def import_assets_command_async(paths: List[str]) -> CommandHandle: ...
+
+
+def export_all_command_async(path: str, target_bytes: int) -> CommandHandle: ...
+
+
+def export_all_asset_tbo_command_async(path: str, target_bytes: int) -> CommandHandle: ...
+
+
+def export_all_tbo_command_async(
+    path: str,
+    target_bytes: int,
+    flags: int,
+    target_point_count: int,
+) -> CommandHandle: ...
+
+
+def embed_all_assets_command_async() -> CommandHandle: ...
+
+
+class Engine:
+    def __init__(
+        self,
+        namespace: Optional[str] = None,
+        config: Optional[SdkConfig] = None,
+    ) -> None: ...
+    @property
+    def namespace(self) -> str: ...
+    @property
+    def config(self) -> SdkConfig: ...
+    def set_config(self, config: SdkConfig) -> None: ...
+    @property
+    def state(self) -> EngineState: ...
+    @property
+    def version(self) -> Optional[str]: ...
+    @property
+    def capabilities(self) -> int: ...
+    def start(
+        self,
+        timeout: Optional[float] = None,
+        config: Optional[EngineLaunchConfig] = None,
+    ) -> None: ...
+    def connect(self, timeout: Optional[float] = None) -> None: ...
+    def stop(self, timeout: Optional[float] = None, drain: bool = True) -> StopOutcome: ...
+    def owns_engine(self) -> Optional[bool]: ...
+    def logs(self, n: int = 100) -> List[str]: ...
+    def set_state_callback(
+        self,
+        callback: Optional[Callable[[EngineState, Optional[str]], None]] = None,
+    ) -> None: ...
+    def set_restart_policy(
+        self,
+        max_restarts: int = 3,
+        initial_backoff: float = 0.5,
+        max_backoff: float = 30.0,
+    ) -> None: ...
+    def set_command_timeout(self, seconds: Optional[float] = None) -> None: ...
+    def get_command_timeout(self) -> Optional[float]: ...
+    def set_max_in_flight(self, max_in_flight: int) -> None: ...
+    def set_mesh_queue(
+        self,
+        capacity: int = 64,
+        policy: MeshQueuePolicy = MeshQueuePolicy.Block,
+    ) -> None: ...
+    def mesh_queue_stats(self) -> MeshQueueStats: ...
+    def poll_mesh_sync(self) -> Optional[AssetSyncContext]: ...
+    def send_transforms(self, uuids: List[bytes], matrices: Any) -> None: ...
+    def poll_mesh_sync_all(self, max: Optional[int] = None) -> Optional[AssetSyncContext]: ...
+    def wait_mesh_sync(self, timeout: Optional[float] = None) -> Optional[AssetSyncContext]: ...
+    def subscribe_mesh_updates(
+        self,
+        callback: Callable[[AssetSyncContext], None],
+    ) -> MeshSubscription: ...
+    def mesh_updates(self, timeout: Optional[float] = None) -> MeshUpdates: ...
+    def prepare_mesh_send(
+        self,
+        vert_counts: List[int],
+        edge_counts: List[int],
+        loop_counts: List[int],
+        total_loop_lengths: List[int],
+        object_counts: List[int],
+        group_names: List[str],
+        surface_contexts: List[int],
+        asset_uuids: List[bytes],
+    ) -> AssetSyncContext: ...
+    def standardize_groups_command(self, uuids: List[bytes]) -> None: ...
+    def standardize_synced_groups_command(
+        self,
+        uuids: List[bytes],
+        surface_contexts: List[int],
+    ) -> None: ...
+    def set_surface_types_command(self, group_surface_map: Dict[bytes, int]) -> None: ...
+    def get_surface_types_command(self) -> Dict[bytes, int]: ...
+    def drop_groups_command(self, uuids: List[bytes]) -> None: ...
+    def drop_all_groups_command(self) -> None: ...
+    def organize_objects_command(self) -> None: ...
+    def group_all_objects_command(self) -> None: ...
+    def extract_geometric_features_command(self, uuids: List[bytes]) -> None: ...
+    def embed_all_assets_command(self) -> None: ...
+    def import_assets_command(self, paths: List[str]) -> None: ...
+    def export_assets_command(self, path: str, target_bytes: int, uuids: List[bytes]) -> None: ...
+    def export_all_command(self, path: str, target_bytes: int) -> None: ...
+    def export_mesh_tbo_command(
+        self,
+        path: str,
+        target_bytes: int,
+        flags: int,
+        uuids: List[bytes],
+    ) -> None: ...
+    def export_asset_tbo_command(self, path: str, target_bytes: int, uuids: List[bytes]) -> None: ...
+    def export_all_asset_tbo_command(self, path: str, target_bytes: int) -> List[str]: ...
+    def export_all_tbo_command(
+        self,
+        path: str,
+        target_bytes: int,
+        flags: int,
+        target_point_count: int,
+    ) -> None: ...
+    def organize_objects_command_async(self) -> CommandHandle: ...
+    def import_assets_command_async(self, paths: List[str]) -> CommandHandle: ...
+    def export_all_command_async(self, path: str, target_bytes: int) -> CommandHandle: ...
+    def export_all_asset_tbo_command_async(self, path: str, target_bytes: int) -> CommandHandle: ...
+    def export_all_tbo_command_async(
+        self,
+        path: str,
+        target_bytes: int,
+        flags: int,
+        target_point_count: int,
+    ) -> CommandHandle: ...
+    def embed_all_assets_command_async(self) -> CommandHandle: ...
+
+
+def default_engine() -> Engine: ...
@@ -0,0 +1,122 @@
+//! Typed NumPy views of an asset's slices (`numpy` feature).
+//!
+//! Arrays are created with `numpy.frombuffer` over the context's guarded memoryviews, so they
+//! are zero-copy, read-only for contexts from `poll_mesh_sync`, and keep the slab mapped
+//! for as long as they live.
+
+use pivot_com_types::fields::Uuid;
+use pyo3::prelude::*;
+
+/// dtype and trailing shape of each slice, in `AssetDataSlices` order
+const ARRAY_LAYOUT: [(&str, &[usize]); 11] = [
+    ("uint8", &[Uuid::SIZE]),
+    ("float32", &[3]),
+    ("uint32", &[2]),
+    ("uint32", &[]),
+    ("uint32", &[]),
+    ("uint32", &[]),
+    ("float32", &[4, 4]),
+    ("uint32", &[]),
+    ("uint32", &[]),
+    ("uint8", &[]),
+    ("float32", &[]),
+];
+
+/// The slices of one asset as typed NumPy arrays over shared memory
+#[pyclass(frozen, get_all)]
+pub struct AssetArrays {
+    /// uint8[K, 32]
+    pub object_uuids: Py<PyAny>,
+    /// float32[N, 3]
+    pub verts: Py<PyAny>,
+    /// uint32[E, 2]
+    pub edges: Py<PyAny>,
+    /// uint32[L]
+    pub loops: Py<PyAny>,
+    /// uint32[...]
+    pub loop_bases: Py<PyAny>,
+    /// uint32[K]
+    pub object_loop_counts: Py<PyAny>,
+    /// float32[K, 4, 4]
+    pub transforms: Py<PyAny>,
+    /// uint32[K]
+    pub vert_counts: Py<PyAny>,
+    /// uint32[K]
+    pub edge_counts: Py<PyAny>,
+    /// uint8[K, W], one fixed-width NUL padded name slot per object
+    pub object_names: Py<PyAny>,
+    /// float32[...]
+    pub embeddings: Py<PyAny>,
+}
+
+impl AssetArrays {
+    /// Wraps the memoryviews of one asset, given in `AssetDataSlices` order
+    pub fn from_views(py: Python, views: [Py<PyAny>; 11]) -> PyResult<AssetArrays> {
+        let numpy = py.import("numpy")?;
+        let mut arrays = Vec::with_capacity(views.len());
+
+        for (view, (dtype, trailing)) in views.into_iter().zip(ARRAY_LAYOUT) {
+            let array = numpy.call_method1("frombuffer", (view, dtype))?;
+            let array = if trailing.is_empty() {
+                array
+            } else {
+                let mut shape = vec![-1isize];
+                shape.extend(trailing.iter().map(|&dim| dim as isize));
+                array.call_method1("reshape", (shape,))?
+            };
+            arrays.push(array.unbind());
+        }
+
+        // Object names are one fixed-width slot per object, so split them by object count
+        let object_count = arrays[0].bind(py).len()?;
+        if object_count > 0 {
+            let names = arrays[9]
+                .bind(py)
+                .call_method1("reshape", ((object_count, -1isize),))?;
+            arrays[9] = names.unbind();
+        }
+
+        let [
+            object_uuids,
+            verts,
+            edges,
+            loops,
+            loop_bases,
+            object_loop_counts,
+            transforms,
+            vert_counts,
+            edge_counts,
+            object_names,
+            embeddings,
+        ]: [Py<PyAny>; 11] = arrays.try_into().unwrap();
+
+        Ok(AssetArrays {
+            object_uuids,
+            verts,
+            edges,
+            loops,
+            loop_bases,
+            object_loop_counts,
+            transforms,
+            vert_counts,
+            edge_counts,
+            object_names,
+            embeddings,
+        })
+    }
+}
+
+#[pymethods]
+impl AssetArrays {
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        let shape = |array: &Py<PyAny>| -> PyResult<String> {
+            Ok(array.bind(py).getattr("shape")?.repr()?.to_string())
+        };
+        Ok(format!(
+            "AssetArrays(verts={}, edges={}, transforms={})",
+            shape(&self.verts)?,
+            shape(&self.edges)?,
+            shape(&self.transforms)?
+        ))
+    }
+}
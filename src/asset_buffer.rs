@@ -0,0 +1,195 @@
+//! Buffer-protocol objects over an AssetSyncContext's shared memory.
+//!
+//! Every memoryview handed to Python is created from an `AssetBuffer`, and every buffer
+//! belongs to the `ViewGuard` of the context it came from. Once the context is sent or
+//! the engine stops, the guard is revoked: new views are refused and the memoryviews we
+//! handed out are released, so touching them raises instead of racing the engine. Views
+//! that cannot be released (because something else, e.g. a numpy array, still exports
+//! them) keep the slab mapped through their `SlabRef`, so they go stale but never dangle.
+//! send() refuses to hand memory to the engine while such views exist; everywhere else
+//! they are reported with a ResourceWarning.
+
+use pyo3::exceptions::{PyBufferError, PyResourceWarning};
+use pyo3::ffi;
+use pyo3::prelude::*;
+use pyo3::types::{PyWeakrefMethods, PyWeakrefReference};
+use std::ffi::{CString, c_int, c_void};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::engine_client::EngineClient;
+use crate::error::SdkError;
+use crate::slab_table::SlabRef;
+
+/// Shared by a context and all of its buffers; revoked when the memory stops being ours
+#[derive(Debug, Default)]
+pub struct ViewGuard {
+    revoked: OnceLock<&'static str>,
+    /// Weak references to the memoryviews handed out so far
+    views: Mutex<Vec<Py<PyWeakrefReference>>>,
+}
+
+impl ViewGuard {
+    pub fn check(&self) -> Result<(), SdkError> {
+        match self.revoked.get() {
+            Some(reason) => Err(SdkError::BufferReleased(reason.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Refuses new views from now on; safe to call without the GIL
+    pub fn revoke(&self, reason: &'static str) {
+        let _ = self.revoked.set(reason);
+    }
+
+    /// Releases the memoryviews still alive and returns how many could not be released
+    /// because something else still exports them; those stay tracked for the next attempt
+    pub fn release_views(&self, py: Python) -> usize {
+        let mut views = self.views.lock().unwrap();
+        views.retain(|view| match view.bind(py).upgrade() {
+            Some(view) => view.call_method0("release").is_err(),
+            None => false,
+        });
+        views.len()
+    }
+
+    /// Like release_views(), but warns about views that stay usable since nothing can be refused
+    pub fn release_views_or_warn(&self, py: Python) {
+        let pinned = self.release_views(py);
+        if pinned == 0 {
+            return;
+        }
+        let message = format!(
+            "{} view(s) into released engine memory are still exported (e.g. by numpy arrays) \
+             and will read stale data",
+            pinned
+        );
+        if let Ok(message) = CString::new(message) {
+            let _ = PyErr::warn(py, &py.get_type::<PyResourceWarning>(), &message, 1);
+        }
+    }
+
+    fn track(&self, view: &Bound<'_, PyAny>) -> PyResult<()> {
+        let py = view.py();
+        let weak = PyWeakrefReference::new(view)?;
+        let mut views = self.views.lock().unwrap();
+        views.retain(|view| view.bind(py).upgrade().is_some());
+        views.push(weak.unbind());
+        Ok(())
+    }
+}
+
+/// One slice of an asset in shared memory, exposed through the buffer protocol
+#[pyclass(unsendable)]
+pub struct AssetBuffer {
+    client: Arc<EngineClient>,
+    guard: Arc<ViewGuard>,
+    slab: SlabRef,
+    data: *mut [u8],
+    name: &'static str,
+    readonly: bool,
+}
+
+impl AssetBuffer {
+    /// Wraps `data` and returns a memoryview over it that the guard can release later
+    pub fn memoryview(
+        py: Python,
+        client: Arc<EngineClient>,
+        guard: Arc<ViewGuard>,
+        slab: SlabRef,
+        data: *mut [u8],
+        name: &'static str,
+        readonly: bool,
+    ) -> PyResult<Py<PyAny>> {
+        let buffer = Bound::new(
+            py,
+            AssetBuffer {
+                client,
+                guard: guard.clone(),
+                slab,
+                data,
+                name,
+                readonly,
+            },
+        )?;
+        let view = unsafe {
+            Bound::from_owned_ptr_or_err(py, ffi::PyMemoryView_FromObject(buffer.as_ptr()))?
+        };
+        guard.track(&view)?;
+        Ok(view.unbind())
+    }
+
+    fn ensure_valid(&self) -> Result<(), SdkError> {
+        self.guard.check()?;
+        if !self.client.slab_is_current(&self.slab) {
+            return Err(SdkError::StaleSlab {
+                slab_index: self.slab.index as u64,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl AssetBuffer {
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        let this = slf.borrow();
+        this.ensure_valid()?;
+        if this.readonly && (flags & ffi::PyBUF_WRITABLE) == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err(format!(
+                "{} belongs to a read-only context",
+                this.name
+            )));
+        }
+
+        let len = this.data.len() as isize;
+        let ok = unsafe {
+            ffi::PyBuffer_FillInfo(
+                view,
+                slf.as_ptr(),
+                this.data as *mut u8 as *mut c_void,
+                len,
+                this.readonly as c_int,
+                flags,
+            )
+        };
+        if ok == -1 {
+            return Err(PyErr::fetch(slf.py()));
+        }
+        Ok(())
+    }
+
+    #[getter]
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    #[getter]
+    fn readonly(&self) -> bool {
+        self.readonly
+    }
+
+    /// False once the context was sent, the engine stopped or the slab was replaced
+    #[getter]
+    fn valid(&self) -> bool {
+        self.ensure_valid().is_ok()
+    }
+
+    fn __len__(&self) -> PyResult<usize> {
+        self.ensure_valid()?;
+        Ok(self.data.len())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "AssetBuffer(name={:?}, nbytes={}, readonly={}, valid={})",
+            self.name,
+            self.data.len(),
+            self.readonly,
+            self.valid()
+        )
+    }
+}
@@ -0,0 +1,155 @@
+//! Text stored in asset memory.
+//!
+//! The group name sits behind the AssetMeta header at `offset_group_name`, with
+//! `group_name_len` bytes reserved for it under `protocol-v1`; otherwise it runs to its NUL
+//! before the first slice. Object names live in the `object_names` slice as one fixed-width
+//! slot per object; a name shorter than its slot is NUL padded.
+
+use crate::error::SdkError;
+use crate::slab_table::bytes_to_clean_str;
+
+pub fn decode_group_name(bytes: &[u8]) -> Result<String, SdkError> {
+    let name = bytes_to_clean_str(bytes);
+    String::from_utf8(name.to_vec())
+        .map_err(|e| SdkError::Decode(format!("group name is not valid UTF-8: {}", e)))
+}
+
+/// Width of each object's name slot in a blob shared by `object_count` objects
+fn slot_width(blob_len: usize, object_count: usize) -> Result<usize, SdkError> {
+    if object_count == 0 {
+        return Ok(0);
+    }
+    if blob_len % object_count != 0 {
+        return Err(SdkError::Decode(format!(
+            "object_names is {} bytes, which does not split into {} name slots",
+            blob_len, object_count
+        )));
+    }
+    Ok(blob_len / object_count)
+}
+
+pub fn decode_object_names(blob: &[u8], object_count: usize) -> Result<Vec<String>, SdkError> {
+    let width = slot_width(blob.len(), object_count)?;
+    if width == 0 {
+        return Ok(vec![String::new(); object_count]);
+    }
+
+    blob.chunks_exact(width)
+        .enumerate()
+        .map(|(i, slot)| {
+            String::from_utf8(bytes_to_clean_str(slot).to_vec()).map_err(|e| {
+                SdkError::Decode(format!("name of object {} is not valid UTF-8: {}", i, e))
+            })
+        })
+        .collect()
+}
+
+/// Writes `names` into their slots, NUL padding each one
+pub fn encode_object_names(
+    blob: &mut [u8],
+    object_count: usize,
+    names: &[String],
+) -> Result<(), SdkError> {
+    if names.len() != object_count {
+        return Err(SdkError::Other(format!(
+            "Expected {} object names, got {}",
+            object_count,
+            names.len()
+        )));
+    }
+    let width = slot_width(blob.len(), object_count)?;
+
+    // Check every name before touching memory so a bad list leaves the old names intact
+    for (i, name) in names.iter().enumerate() {
+        if name.len() > width {
+            return Err(SdkError::Other(format!(
+                "Name of object {} is {} bytes, but name slots are {} bytes",
+                i,
+                name.len(),
+                width
+            )));
+        }
+        if name.as_bytes().contains(&0) {
+            return Err(SdkError::Other(format!(
+                "Name of object {} contains a NUL byte",
+                i
+            )));
+        }
+    }
+
+    if width == 0 {
+        return Ok(());
+    }
+    for (slot, name) in blob.chunks_exact_mut(width).zip(names) {
+        slot.fill(0);
+        slot[..name.len()].copy_from_slice(name.as_bytes());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn object_names_round_trip() {
+        let mut blob = [0xffu8; 24];
+        let written = names(&["Cube", "", "Suzanne"]);
+        encode_object_names(&mut blob, 3, &written).unwrap();
+
+        assert_eq!(&blob[..8], b"Cube\0\0\0\0");
+        assert_eq!(decode_object_names(&blob, 3).unwrap(), written);
+    }
+
+    #[test]
+    fn full_slot_needs_no_terminator() {
+        let mut blob = [0u8; 8];
+        encode_object_names(&mut blob, 2, &names(&["Left", "Ring"])).unwrap();
+        assert_eq!(
+            decode_object_names(&blob, 2).unwrap(),
+            names(&["Left", "Ring"])
+        );
+    }
+
+    #[test]
+    fn no_objects_decode_to_no_names() {
+        assert!(decode_object_names(&[], 0).unwrap().is_empty());
+        assert_eq!(decode_object_names(&[], 2).unwrap(), names(&["", ""]));
+    }
+
+    #[test]
+    fn invalid_utf8_is_a_decode_error() {
+        let blob = [b'o', b'k', 0, 0, 0xc3, 0x28, 0, 0];
+        assert!(matches!(
+            decode_object_names(&blob, 2),
+            Err(SdkError::Decode(_))
+        ));
+        assert!(matches!(
+            decode_group_name(&[0xff, 0]),
+            Err(SdkError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn uneven_blob_is_a_decode_error() {
+        assert!(matches!(
+            decode_object_names(&[0u8; 10], 3),
+            Err(SdkError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn bad_names_leave_the_blob_untouched() {
+        let mut blob = *b"Cube\0\0\0\0Cone\0\0\0\0";
+
+        assert!(encode_object_names(&mut blob, 2, &names(&["Cube"])).is_err());
+        assert!(encode_object_names(&mut blob, 2, &names(&["Sphere", "Cylinder!"])).is_err());
+        assert!(encode_object_names(&mut blob, 2, &names(&["Plane", "Nu\0ll"])).is_err());
+
+        assert_eq!(&blob, b"Cube\0\0\0\0Cone\0\0\0\0");
+    }
+}
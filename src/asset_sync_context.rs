@@ -2,38 +2,215 @@ use pivot_com_types::{
     asset_meta::{AssetDataSlices, AssetMeta},
     asset_ptr::AssetPtr, fields::Uuid,
 };
-use pyo3::{ffi, prelude::*, types::PyByteArray};
-use std::{os::raw::c_char, ptr::NonNull};
+use pyo3::{
+    prelude::*,
+    types::{PyByteArray, PyIterator, PyList},
+};
+use std::{ptr::NonNull, sync::Arc};
 
+#[cfg(feature = "numpy")]
+use crate::asset_arrays::AssetArrays;
+use crate::asset_buffer::{AssetBuffer, ViewGuard};
+use crate::asset_names::{decode_group_name, decode_object_names, encode_object_names};
+use crate::asset_validation::SLICE_LAYOUT;
+use crate::asset_view::AssetView;
 use crate::engine_api;
+use crate::engine_client::EngineClient;
+use crate::error::SdkError;
+use crate::slab_table::SlabRef;
 
-#[pyclass(unsendable)]
+#[pyclass]
 pub struct AssetSyncContext {
+    /// Engine the memory belongs to; send() goes back to the same engine
+    client: Arc<EngineClient>,
     asset_slices: Vec<AssetDataSlices>,
+    /// Mapping each asset's memory came from, checked before handing out views
+    slab_refs: Vec<SlabRef>,
+    /// Revoked by send() or engine stop; every view handed out belongs to it
+    guard: Arc<ViewGuard>,
+    /// Contexts received from the engine only hand out read-only views
+    readonly: bool,
+    /// The engine only moved objects; every slice but transforms is unchanged
+    transforms_only: bool,
     asset_ptrs: Vec<AssetPtr>,
     asset_uuids: Vec<Uuid>,
-    asset_surface_contexts: Vec<u16>
+    asset_surface_contexts: Vec<u16>,
+    asset_group_names: Vec<String>,
 }
 
+// Built without the GIL and handed to Python on whichever thread receives it (mesh update
+// subscribers run on their own dispatcher thread). The slice pointers target process-wide
+// mappings kept alive by `slab_refs`. Rust only writes through them from `&mut self`
+// methods, which pyo3's borrow checking keeps exclusive, so a shared context is only read.
+unsafe impl Send for AssetSyncContext {}
+unsafe impl Sync for AssetSyncContext {}
+
 impl AssetSyncContext {
-    pub fn new(ptrs: Vec<NonNull<AssetMeta>>, asset_ptrs: &[AssetPtr]) -> AssetSyncContext {
+    pub fn new(
+        client: Arc<EngineClient>,
+        ptrs: Vec<NonNull<AssetMeta>>,
+        slab_refs: Vec<SlabRef>,
+        asset_ptrs: &[AssetPtr],
+        readonly: bool,
+    ) -> Result<AssetSyncContext, SdkError> {
         let mut asset_slices = Vec::with_capacity(ptrs.len());
         let mut asset_uuids = Vec::with_capacity(ptrs.len());
         let mut asset_surface_contexts = Vec::with_capacity(ptrs.len());
+        let mut asset_group_names = Vec::with_capacity(ptrs.len());
 
-        for mut ptr in ptrs {
-            asset_slices.push(unsafe { ptr.as_mut().get_slices() });
+        for (mut ptr, slab) in ptrs.into_iter().zip(&slab_refs) {
+            let slices = unsafe { ptr.as_mut().get_slices() };
             asset_uuids.push(unsafe {ptr.as_mut().uuid});
             asset_surface_contexts.push(unsafe {ptr.as_mut().surface_context});
+            asset_group_names.push(unsafe { read_group_name(ptr, &slices, slab) }?);
+            asset_slices.push(slices);
         }
 
-        AssetSyncContext {
+        let guard = Arc::new(ViewGuard::default());
+        client.track_views(&guard);
+
+        Ok(AssetSyncContext {
+            client,
             asset_slices,
+            slab_refs,
+            guard,
+            readonly,
+            transforms_only: false,
             asset_ptrs: asset_ptrs.to_vec(),
             asset_uuids,
             asset_surface_contexts,
+            asset_group_names,
+        })
+    }
+
+    /// Marks a received context whose update left the geometry untouched
+    pub fn set_transforms_only(&mut self, transforms_only: bool) {
+        self.transforms_only = transforms_only;
+    }
+
+    pub fn uuid(&self, i: usize) -> Uuid {
+        self.asset_uuids[i]
+    }
+
+    pub fn surface_context(&self, i: usize) -> u16 {
+        self.asset_surface_contexts[i]
+    }
+
+    pub fn group_name(&self, i: usize) -> &str {
+        &self.asset_group_names[i]
+    }
+
+    /// Fails once the context was sent or the engine stopped; views still alive are released then
+    fn ensure_usable(&self, py: Python) -> Result<(), SdkError> {
+        self.guard.check().inspect_err(|_| self.guard.release_views_or_warn(py))
+    }
+
+    /// Fails if the engine unmapped or replaced the slab asset `i` lives in
+    fn ensure_current(&self, i: usize) -> Result<(), SdkError> {
+        match self.slab_refs.get(i) {
+            Some(slab) if !self.client.slab_is_current(slab) => Err(SdkError::StaleSlab {
+                slab_index: slab.index as u64,
+            }),
+            _ => Ok(()),
         }
     }
+
+    /// Memoryviews over every slice of asset `i`, in `AssetDataSlices` order
+    fn views(&self, py: Python, i: usize) -> PyResult<[Py<PyAny>; 11]> {
+        let g = self.asset_slices.get(i).ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyIndexError, _>(format!("index {} out of range", i))
+        })?;
+        self.ensure_usable(py)?;
+        self.ensure_current(i)?;
+
+        Ok([
+            self.view(py, i, 0, g.0)?,
+            self.view(py, i, 1, g.1)?,
+            self.view(py, i, 2, g.2)?,
+            self.view(py, i, 3, g.3)?,
+            self.view(py, i, 4, g.4)?,
+            self.view(py, i, 5, g.5)?,
+            self.view(py, i, 6, g.6)?,
+            self.view(py, i, 7, g.7)?,
+            self.view(py, i, 8, g.8)?,
+            self.view(py, i, 9, g.9)?,
+            self.view(py, i, 10, g.10)?,
+        ])
+    }
+
+    /// Copies `bytes` into slice `slice` of asset `i`, which must be exactly as large
+    pub fn write_slice(&mut self, i: usize, slice: usize, bytes: &[u8]) -> Result<(), SdkError> {
+        let dest = unsafe { &mut *self.writable_slice(i, slice)? };
+        if dest.len() != bytes.len() {
+            return Err(SdkError::Other(format!(
+                "{} of asset {} holds {} bytes, got {}",
+                SLICE_LAYOUT[slice].0,
+                i,
+                dest.len(),
+                bytes.len()
+            )));
+        }
+        dest.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Zero-fills slice `slice` of asset `i`
+    pub fn clear_slice(&mut self, i: usize, slice: usize) -> Result<(), SdkError> {
+        unsafe { &mut *self.writable_slice(i, slice)? }.fill(0);
+        Ok(())
+    }
+
+    /// Slice `slice` of asset `i`, once the context is known to be writable and current
+    fn writable_slice(&mut self, i: usize, slice: usize) -> Result<*mut [u8], SdkError> {
+        if self.readonly {
+            return Err(SdkError::Other(
+                "Cannot write into a read-only context".to_string(),
+            ));
+        }
+        self.guard.check()?;
+        self.ensure_current(i)?;
+
+        let g = &self.asset_slices[i];
+        Ok([g.0, g.1, g.2, g.3, g.4, g.5, g.6, g.7, g.8, g.9, g.10][slice])
+    }
+
+    /// Writes object names into asset `i` without Python; see set_object_names
+    pub fn write_object_names(&mut self, i: usize, names: &[String]) -> Result<(), SdkError> {
+        let blob = unsafe { &mut *self.writable_slice(i, 9)? };
+        encode_object_names(blob, self.asset_slices[i].0.len() / Uuid::SIZE, names)
+    }
+
+    /// The object_names slice of asset `i` and how many objects share it
+    fn object_name_slots(&self, py: Python, i: usize) -> PyResult<(*mut [u8], usize)> {
+        let g = self.asset_slices.get(i).ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyIndexError, _>(format!("index {} out of range", i))
+        })?;
+        self.ensure_usable(py)?;
+        self.ensure_current(i)?;
+        Ok((g.9, g.0.len() / Uuid::SIZE))
+    }
+
+    /// A memoryview over slice `slice` (an `AssetDataSlices` index) of asset `i`
+    pub fn slice_view(&self, py: Python, i: usize, slice: usize) -> PyResult<Py<PyAny>> {
+        let g = &self.asset_slices[i];
+        let data = [g.0, g.1, g.2, g.3, g.4, g.5, g.6, g.7, g.8, g.9, g.10][slice];
+        self.ensure_usable(py)?;
+        self.ensure_current(i)?;
+        self.view(py, i, slice, data)
+    }
+
+    /// A memoryview over one slice of asset `i`, released when the context is sent
+    fn view(&self, py: Python, i: usize, slice: usize, data: *mut [u8]) -> PyResult<Py<PyAny>> {
+        AssetBuffer::memoryview(
+            py,
+            self.client.clone(),
+            self.guard.clone(),
+            self.slab_refs[i].clone(),
+            data,
+            SLICE_LAYOUT[slice].0,
+            self.readonly,
+        )
+    }
 }
 
 #[pymethods]
@@ -72,22 +249,19 @@ impl AssetSyncContext {
         Py<PyAny>,
         Py<PyAny>,
     )> {
-        let g = self.asset_slices.get(i).ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyIndexError, _>(format!("index {} out of range", i))
-        })?;
-
-        // Tuple order: (obj_uuids, verts, edges, loops, loop_bases, object_loop_counts, transforms, vert_counts, edge_counts, object_names, embeddings)
-        let obj_uuids = memoryview_from_slice(py, g.0)?;
-        let verts = memoryview_from_slice(py, g.1)?;
-        let edges = memoryview_from_slice(py, g.2)?;
-        let loops = memoryview_from_slice(py, g.3)?;
-        let loop_bases = memoryview_from_slice(py, g.4)?;
-        let object_loop_counts = memoryview_from_slice(py, g.5)?;
-        let transforms = memoryview_from_slice(py, g.6)?;
-        let vert_counts = memoryview_from_slice(py, g.7)?;
-        let edge_counts = memoryview_from_slice(py, g.8)?;
-        let object_names = memoryview_from_slice(py, g.9)?;
-        let embeddings = memoryview_from_slice(py, g.10)?;
+        let [
+            obj_uuids,
+            verts,
+            edges,
+            loops,
+            loop_bases,
+            object_loop_counts,
+            transforms,
+            vert_counts,
+            edge_counts,
+            object_names,
+            embeddings,
+        ] = self.views(py, i)?;
 
         Ok((
             verts,
@@ -104,28 +278,133 @@ impl AssetSyncContext {
         ))
     }
 
+    /// Typed, zero-copy NumPy arrays over the slices of asset `i`
+    #[cfg(feature = "numpy")]
+    pub fn arrays(&self, py: Python, i: usize) -> PyResult<AssetArrays> {
+        AssetArrays::from_views(py, self.views(py, i)?)
+    }
+
+    /// Group name of every asset, in context order
+    pub fn group_names(&self) -> Vec<String> {
+        self.asset_group_names.clone()
+    }
+
+    /// Names of the objects of asset `i`, decoded from the object_names slice
+    pub fn object_names(&self, py: Python, i: usize) -> PyResult<Vec<String>> {
+        let (blob, object_count) = self.object_name_slots(py, i)?;
+        Ok(decode_object_names(unsafe { &*blob }, object_count)?)
+    }
+
+    /// Fills the object_names slice of asset `i`, one name per object
+    pub fn set_object_names(&mut self, py: Python, i: usize, names: Vec<String>) -> PyResult<()> {
+        if self.readonly {
+            return Err(SdkError::Other(
+                "Cannot write object names into a read-only context".to_string(),
+            )
+            .into());
+        }
+        let (blob, object_count) = self.object_name_slots(py, i)?;
+        encode_object_names(unsafe { &mut *blob }, object_count, &names)?;
+        Ok(())
+    }
+
     pub fn size(&self) -> usize {
         self.asset_slices.len()
     }
 
-    pub fn send(&mut self) -> () {
-        let response = engine_api::send_mesh_command(std::mem::take(&mut self.asset_ptrs));
+    fn __len__(&self) -> usize {
+        self.asset_slices.len()
+    }
 
-        if response.is_err() {
-            println!("{:?}", response.err());
+    /// Named view of asset `i`; negative indices count from the end
+    fn __getitem__(slf: Bound<'_, Self>, i: isize) -> PyResult<AssetView> {
+        let len = slf.borrow().asset_slices.len();
+        let index = if i < 0 { i + len as isize } else { i };
+        if index < 0 || index as usize >= len {
+            return Err(PyErr::new::<pyo3::exceptions::PyIndexError, _>(format!(
+                "index {} out of range",
+                i
+            )));
         }
+        Ok(AssetView::new(slf.unbind(), index as usize))
+    }
+
+    fn __iter__(slf: Bound<'_, Self>) -> PyResult<Bound<'_, PyIterator>> {
+        let py = slf.py();
+        let len = slf.borrow().asset_slices.len();
+        let views = (0..len)
+            .map(|i| AssetView::new(slf.clone().unbind(), i))
+            .collect::<Vec<_>>();
+        PyList::new(py, views)?.try_iter()
+    }
+
+    /// Whether the buffers of this context are read-only (contexts from poll_mesh_sync)
+    #[getter]
+    pub fn readonly(&self) -> bool {
+        self.readonly
+    }
+
+    /// True if the update only moved objects; only the transforms slices need re-reading
+    #[getter]
+    pub fn transforms_only(&self) -> bool {
+        self.transforms_only
+    }
+
+    /// False once the context was sent or its engine stopped
+    #[getter]
+    pub fn valid(&self) -> bool {
+        self.guard.check().is_ok()
     }
-}
 
-fn memoryview_from_slice(py: Python, slice_ptr: *mut [u8]) -> PyResult<Py<PyAny>> {
-    let ptr = slice_ptr as *mut u8 as *mut c_char;
-    let len = unsafe { (&*slice_ptr).len() } as isize;
-    let mv = unsafe { ffi::PyMemoryView_FromMemory(ptr, len, ffi::PyBUF_WRITE) };
-    if mv.is_null() {
-        return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-            "failed to create memoryview from slice",
-        ));
+    /// Hands the assets to the engine. Every buffer of this context is released first,
+    /// since the engine owns the memory from here on; fails while a view into it is
+    /// still exported (e.g. a numpy array), so delete those and send again. If the send
+    /// itself fails the context stays usable and can be sent again
+    pub fn send(&mut self, py: Python) -> PyResult<()> {
+        if self.readonly {
+            return Err(SdkError::Other(
+                "Cannot send a read-only context; it belongs to the engine".to_string(),
+            )
+            .into());
+        }
+        self.ensure_usable(py)?;
+        let pinned = self.guard.release_views(py);
+        if pinned > 0 {
+            return Err(SdkError::ViewsInUse(pinned).into());
+        }
+        let asset_ptrs = self.asset_ptrs.clone();
+        py.detach(|| engine_api::send_mesh_command(&self.client, asset_ptrs))?;
+        self.guard.revoke("its context was sent to the engine");
+        self.asset_ptrs.clear();
+        Ok(())
     }
-    Ok(unsafe { Py::from_owned_ptr(py, mv) })
 }
 
+/// The group name AssetMeta::new reserved room for; its start was checked on hydration
+#[cfg_attr(feature = "protocol-v1", allow(unused_variables))]
+unsafe fn read_group_name(
+    meta: NonNull<AssetMeta>,
+    slices: &AssetDataSlices,
+    slab: &SlabRef,
+) -> Result<String, SdkError> {
+    let meta_ref = unsafe { meta.as_ref() };
+    let start = unsafe { (meta.as_ptr() as *const u8).add(meta_ref.offset_group_name as usize) };
+    #[cfg(feature = "protocol-v1")]
+    let len = meta_ref.group_name_len as usize;
+    #[cfg(not(feature = "protocol-v1"))]
+    let len = group_name_room(start, slices, slab);
+    decode_group_name(unsafe { std::slice::from_raw_parts(start, len) })
+}
+
+/// Bytes from `start` to the first slice behind it, or to the end of the slab. Without
+/// `group_name_len` the name is only known to end at its NUL somewhere in that room
+#[cfg(not(feature = "protocol-v1"))]
+fn group_name_room(start: *const u8, slices: &AssetDataSlices, slab: &SlabRef) -> usize {
+    let g = slices;
+    [g.0, g.1, g.2, g.3, g.4, g.5, g.6, g.7, g.8, g.9, g.10]
+        .iter()
+        .map(|slice| *slice as *const u8 as usize)
+        .filter(|&slice_start| slice_start >= start as usize)
+        .fold(slab.end() as usize, usize::min)
+        .saturating_sub(start as usize)
+}
@@ -0,0 +1,185 @@
+//! Checks applied to every AssetPtr before the SDK dereferences it.
+//!
+//! Bounds and alignment are always checked: the AssetMeta header and every slice it
+//! describes must lie inside the slab the pointer names. Engines with `CAP_ASSET_MAGIC`
+//! stamp `AssetMeta::MAGIC` into each header; with the `safe-mode` feature the header
+//! checksum is verified as well.
+//!
+//! The magic, the name length and the raw slice offsets need `protocol-v1`. Without it a
+//! corrupt header is only caught once `get_slices()` has turned it into slices.
+
+use pivot_com_types::asset_meta::AssetMeta;
+use std::mem::{align_of, size_of};
+use std::ptr::NonNull;
+
+use crate::error::SdkError;
+
+/// Name and element alignment of each slice, in `AssetDataSlices` order
+pub const SLICE_LAYOUT: [(&str, usize); 11] = [
+    ("object_uuids", 1),
+    ("verts", align_of::<f32>()),
+    ("edges", align_of::<u32>()),
+    ("loops", align_of::<u32>()),
+    ("loop_bases", align_of::<u32>()),
+    ("object_loop_counts", align_of::<u32>()),
+    ("transforms", align_of::<f32>()),
+    ("vert_counts", align_of::<u32>()),
+    ("edge_counts", align_of::<u32>()),
+    ("object_names", 1),
+    ("embeddings", align_of::<f32>()),
+];
+
+/// The header fields locating each slice, relative to the header, in SLICE_LAYOUT order
+#[cfg(feature = "protocol-v1")]
+fn slice_offsets(meta: &AssetMeta) -> [u64; 11] {
+    [
+        meta.offset_object_uuids as u64,
+        meta.offset_verts as u64,
+        meta.offset_edges as u64,
+        meta.offset_loops as u64,
+        meta.offset_loop_bases as u64,
+        meta.offset_object_loop_counts as u64,
+        meta.offset_transforms as u64,
+        meta.offset_vert_counts as u64,
+        meta.offset_edge_counts as u64,
+        meta.offset_object_names as u64,
+        meta.offset_embeddings as u64,
+    ]
+}
+
+/// A mapped slab an AssetPtr points into
+pub struct SlabBounds {
+    pub index: usize,
+    pub base: *mut u8,
+    pub size: usize,
+}
+
+/// Checks that `len` bytes at `offset` fit in the slab and that an AssetMeta there is aligned
+pub fn validate_allocation(
+    slab: &SlabBounds,
+    offset: u64,
+    len: u64,
+) -> Result<*mut AssetMeta, SdkError> {
+    let end = offset.checked_add(len);
+    if end.is_none_or(|end| end > slab.size as u64) {
+        return Err(SdkError::AssetPtrOutOfBounds {
+            slab_index: slab.index as u64,
+            offset,
+            reason: format!("{} bytes do not fit in the {} byte slab", len, slab.size),
+        });
+    }
+
+    let meta_ptr = unsafe { slab.base.add(offset as usize) } as *mut AssetMeta;
+    if !meta_ptr.is_aligned() {
+        return Err(SdkError::CorruptAsset {
+            slab_index: slab.index as u64,
+            offset,
+            reason: format!("AssetMeta is not {}-byte aligned", align_of::<AssetMeta>()),
+        });
+    }
+    Ok(meta_ptr)
+}
+
+/// Validates the AssetMeta at `offset` and everything it points to, returning the header pointer
+#[cfg_attr(not(feature = "protocol-v1"), allow(unused_variables))]
+pub fn validate_asset(
+    slab: &SlabBounds,
+    offset: u64,
+    check_magic: bool,
+) -> Result<NonNull<AssetMeta>, SdkError> {
+    let corrupt = |reason: String| SdkError::CorruptAsset {
+        slab_index: slab.index as u64,
+        offset,
+        reason,
+    };
+
+    let meta_ptr = validate_allocation(slab, offset, size_of::<AssetMeta>() as u64)?;
+    let meta = unsafe { &mut *meta_ptr };
+
+    #[cfg(feature = "protocol-v1")]
+    if check_magic && meta.magic != AssetMeta::MAGIC {
+        return Err(corrupt(format!(
+            "bad magic {:#010x} (expected {:#010x})",
+            meta.magic,
+            AssetMeta::MAGIC
+        )));
+    }
+
+    #[cfg(feature = "safe-mode")]
+    if check_magic && meta.checksum != meta.compute_checksum() {
+        return Err(corrupt(format!(
+            "header checksum {:#018x} does not match its contents ({:#018x})",
+            meta.checksum,
+            meta.compute_checksum()
+        )));
+    }
+
+    #[cfg(feature = "protocol-v1")]
+    let name_end = offset
+        .checked_add(meta.offset_group_name as u64)
+        .and_then(|start| start.checked_add(meta.group_name_len as u64));
+    // Without the length only the start is known; read_group_name bounds the rest
+    #[cfg(not(feature = "protocol-v1"))]
+    let name_end = offset.checked_add(meta.offset_group_name as u64);
+    if name_end.is_none_or(|end| end > slab.size as u64) {
+        return Err(SdkError::AssetPtrOutOfBounds {
+            slab_index: slab.index as u64,
+            offset,
+            reason: format!(
+                "group name at header offset {} extends past the {} byte slab",
+                meta.offset_group_name, slab.size
+            ),
+        });
+    }
+
+    // get_slices() offsets pointers by these fields, so they have to stay inside the slab
+    // before it may be called at all
+    #[cfg(feature = "protocol-v1")]
+    for ((name, _), field) in SLICE_LAYOUT.iter().zip(slice_offsets(meta)) {
+        if offset
+            .checked_add(field)
+            .is_none_or(|start| start > slab.size as u64)
+        {
+            return Err(SdkError::AssetPtrOutOfBounds {
+                slab_index: slab.index as u64,
+                offset,
+                reason: format!(
+                    "{} starts at header offset {}, past the {} byte slab",
+                    name, field, slab.size
+                ),
+            });
+        }
+    }
+
+    let s = meta.get_slices();
+    let slices = [s.0, s.1, s.2, s.3, s.4, s.5, s.6, s.7, s.8, s.9, s.10];
+    let slab_start = slab.base as usize;
+    let slab_end = slab_start + slab.size;
+
+    for (slice, (name, align)) in slices.iter().zip(SLICE_LAYOUT) {
+        let start = *slice as *mut u8 as usize;
+        let len = slice.len();
+        let in_bounds = start >= slab_start
+            && start
+                .checked_add(len)
+                .is_some_and(|end| end <= slab_end);
+        if !in_bounds {
+            return Err(SdkError::AssetPtrOutOfBounds {
+                slab_index: slab.index as u64,
+                offset,
+                reason: format!(
+                    "{} ({} bytes at slab offset {}) extends past the {} byte slab",
+                    name,
+                    len,
+                    start.wrapping_sub(slab_start),
+                    slab.size
+                ),
+            });
+        }
+        if len > 0 && (start % align != 0 || len % align != 0) {
+            return Err(corrupt(format!("{} is not {}-byte aligned", name, align)));
+        }
+    }
+
+    Ok(unsafe { NonNull::new_unchecked(meta_ptr) })
+}
@@ -0,0 +1,124 @@
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::asset_sync_context::AssetSyncContext;
+
+/// One asset of an AssetSyncContext with its slices as named memoryviews.
+///
+/// Views share the context's lifetime rules: they are released once the context is sent
+/// or its engine stops, and are read-only for contexts from poll_mesh_sync.
+#[pyclass(unsendable)]
+pub struct AssetView {
+    context: Py<AssetSyncContext>,
+    index: usize,
+}
+
+impl AssetView {
+    pub fn new(context: Py<AssetSyncContext>, index: usize) -> Self {
+        AssetView { context, index }
+    }
+
+    fn slice(&self, py: Python, slice: usize) -> PyResult<Py<PyAny>> {
+        self.context.borrow(py).slice_view(py, self.index, slice)
+    }
+}
+
+#[pymethods]
+impl AssetView {
+    #[getter]
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    #[getter]
+    fn uuid<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.context.borrow(py).uuid(self.index).bytes)
+    }
+
+    #[getter]
+    fn surface_context(&self, py: Python) -> u16 {
+        self.context.borrow(py).surface_context(self.index)
+    }
+
+    #[getter]
+    fn group_name(&self, py: Python) -> String {
+        self.context.borrow(py).group_name(self.index).to_string()
+    }
+
+    /// Object names decoded from the object_names slice
+    #[getter]
+    fn names(&self, py: Python) -> PyResult<Vec<String>> {
+        self.context.borrow(py).object_names(py, self.index)
+    }
+
+    fn set_object_names(&self, py: Python, names: Vec<String>) -> PyResult<()> {
+        self.context
+            .borrow_mut(py)
+            .set_object_names(py, self.index, names)
+    }
+
+    #[getter]
+    fn object_uuids(&self, py: Python) -> PyResult<Py<PyAny>> {
+        self.slice(py, 0)
+    }
+
+    #[getter]
+    fn verts(&self, py: Python) -> PyResult<Py<PyAny>> {
+        self.slice(py, 1)
+    }
+
+    #[getter]
+    fn edges(&self, py: Python) -> PyResult<Py<PyAny>> {
+        self.slice(py, 2)
+    }
+
+    #[getter]
+    fn loops(&self, py: Python) -> PyResult<Py<PyAny>> {
+        self.slice(py, 3)
+    }
+
+    #[getter]
+    fn loop_bases(&self, py: Python) -> PyResult<Py<PyAny>> {
+        self.slice(py, 4)
+    }
+
+    #[getter]
+    fn object_loop_counts(&self, py: Python) -> PyResult<Py<PyAny>> {
+        self.slice(py, 5)
+    }
+
+    #[getter]
+    fn transforms(&self, py: Python) -> PyResult<Py<PyAny>> {
+        self.slice(py, 6)
+    }
+
+    #[getter]
+    fn vert_counts(&self, py: Python) -> PyResult<Py<PyAny>> {
+        self.slice(py, 7)
+    }
+
+    #[getter]
+    fn edge_counts(&self, py: Python) -> PyResult<Py<PyAny>> {
+        self.slice(py, 8)
+    }
+
+    #[getter]
+    fn object_names(&self, py: Python) -> PyResult<Py<PyAny>> {
+        self.slice(py, 9)
+    }
+
+    #[getter]
+    fn embeddings(&self, py: Python) -> PyResult<Py<PyAny>> {
+        self.slice(py, 10)
+    }
+
+    fn __repr__(&self, py: Python) -> String {
+        let context = self.context.borrow(py);
+        format!(
+            "AssetView(index={}, group_name={:?}, surface_context={})",
+            self.index,
+            context.group_name(self.index),
+            context.surface_context(self.index)
+        )
+    }
+}
@@ -0,0 +1,132 @@
+//! Bridges blocking SDK waits to asyncio.
+//!
+//! Yielding None from `__await__` makes the event loop reschedule the task immediately, so
+//! awaiting would spin a core. Instead the wait runs on a helper thread without the GIL and
+//! hands its outcome to an `asyncio.Future` on the awaiting task's loop.
+
+use pyo3::prelude::*;
+use pyo3::types::{PyCFunction, PyDict, PyTuple};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::error::SdkError;
+
+/// An `asyncio.Future` on the running event loop, to be resolved from another thread
+pub struct PendingFuture {
+    event_loop: Py<PyAny>,
+    future: Py<PyAny>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl PendingFuture {
+    /// Creates the future on the running loop; the Python side gets the returned object
+    pub fn new<'py>(py: Python<'py>) -> PyResult<(Self, Bound<'py, PyAny>)> {
+        let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+        let future = event_loop.call_method0("create_future")?;
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = cancelled.clone();
+        let on_done = PyCFunction::new_closure(
+            py,
+            None,
+            None,
+            move |args: &Bound<'_, PyTuple>, _kwargs: Option<&Bound<'_, PyDict>>| -> PyResult<()> {
+                if args.get_item(0)?.call_method0("cancelled")?.is_truthy()? {
+                    flag.store(true, Ordering::SeqCst);
+                }
+                Ok(())
+            },
+        )?;
+        future.call_method1("add_done_callback", (on_done,))?;
+
+        let pending = PendingFuture {
+            event_loop: event_loop.unbind(),
+            future: future.clone().unbind(),
+            cancelled,
+        };
+        Ok((pending, future))
+    }
+
+    /// Turns true once the awaiting task was cancelled
+    pub fn cancelled(&self) -> &AtomicBool {
+        &self.cancelled
+    }
+
+    /// Hands `outcome` to the future on its loop. If the future is already done by the time
+    /// the loop gets to it (the task was cancelled meanwhile), `unclaimed` runs instead
+    pub fn resolve<U>(self, py: Python<'_>, outcome: PyResult<Py<PyAny>>, unclaimed: U)
+    where
+        U: FnOnce() + Send + 'static,
+    {
+        let (value, failed) = match outcome {
+            Ok(value) => (value, false),
+            Err(e) => (e.into_value(py).into_any(), true),
+        };
+        let target = self.future;
+        let unclaimed = Mutex::new(Some(unclaimed));
+        let give_back = move || {
+            if let Some(unclaimed) = unclaimed.lock().unwrap().take() {
+                unclaimed();
+            }
+        };
+        let resolve = PyCFunction::new_closure(
+            py,
+            None,
+            None,
+            move |args: &Bound<'_, PyTuple>, _kwargs: Option<&Bound<'_, PyDict>>| -> PyResult<()> {
+                let py = args.py();
+                let target = target.bind(py);
+                // The task may have been cancelled while the callback was queued
+                if target.call_method0("done")?.is_truthy()? {
+                    give_back();
+                    return Ok(());
+                }
+                let method = if failed {
+                    "set_exception"
+                } else {
+                    "set_result"
+                };
+                target.call_method1(method, (value.clone_ref(py),))?;
+                Ok(())
+            },
+        );
+        // Only fails once the loop is closed, and then nobody is awaiting any more
+        if let Ok(resolve) = resolve {
+            let _ = self
+                .event_loop
+                .call_method1(py, "call_soon_threadsafe", (resolve,));
+        }
+    }
+}
+
+/// Returns a future on the running event loop that resolves with the outcome of `wait`.
+///
+/// `wait` runs on its own thread without the GIL; it is handed a flag that turns true once the
+/// awaiting task was cancelled, so it can give up early. `finish` converts its outcome into
+/// a Python value (or the exception the future raises) with the GIL held.
+pub fn spawn_future<'py, T, W, F>(
+    py: Python<'py>,
+    name: &str,
+    wait: W,
+    finish: F,
+) -> PyResult<Bound<'py, PyAny>>
+where
+    T: Send + 'static,
+    W: FnOnce(&AtomicBool) -> T + Send + 'static,
+    F: FnOnce(Python<'_>, T) -> PyResult<Py<PyAny>> + Send + 'static,
+{
+    let (pending, future) = PendingFuture::new(py)?;
+    thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || {
+            let outcome = wait(pending.cancelled());
+            Python::attach(|py| {
+                let outcome = finish(py, outcome);
+                pending.resolve(py, outcome, || {});
+            });
+        })
+        .map_err(|e| SdkError::Other(format!("Failed to spawn {} thread: {}", name, e)))?;
+
+    Ok(future)
+}
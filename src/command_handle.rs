@@ -0,0 +1,109 @@
+use pivot_com_types::EngineResponse;
+use pyo3::prelude::*;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use crate::asyncio_future::spawn_future;
+use crate::command_options::duration_from_secs;
+use crate::engine_client::PendingCommand;
+use crate::error::SdkError;
+
+/// How a finished command's response is turned into a Python value
+#[derive(Clone, Copy)]
+pub enum ResponseDecoder {
+    /// The command has no payload, result() returns None
+    Unit,
+    /// The engine answers with the list of files it wrote
+    FileList,
+}
+
+impl ResponseDecoder {
+    fn decode(self, py: Python, resp: &EngineResponse) -> PyResult<Py<PyAny>> {
+        match self {
+            ResponseDecoder::Unit => Ok(py.None()),
+            ResponseDecoder::FileList => {
+                let filenames = resp.read_tbo_flush().map_err(|e| {
+                    SdkError::Decode(format!("Failed to read flush response: {}", e))
+                })?;
+                let result: Vec<String> = filenames.into_iter().map(|s| s.to_string()).collect();
+                Ok(result.into_pyobject(py)?.into_any().unbind())
+            }
+        }
+    }
+}
+
+/// How often an awaiting thread checks whether its task was cancelled
+const AWAIT_CANCEL_CHECK: Duration = Duration::from_millis(50);
+
+/// Handle to an engine command running in the background.
+///
+/// Poll it with done(), block on it with result(timeout), or `await` it from asyncio.
+#[pyclass(frozen)]
+pub struct CommandHandle {
+    pending: Arc<PendingCommand>,
+    decoder: ResponseDecoder,
+}
+
+impl CommandHandle {
+    pub fn new(pending: PendingCommand, decoder: ResponseDecoder) -> Self {
+        CommandHandle {
+            pending: Arc::new(pending),
+            decoder,
+        }
+    }
+}
+
+#[pymethods]
+impl CommandHandle {
+    /// Whether the engine has answered (or the command failed, timed out or was cancelled).
+    fn done(&self) -> bool {
+        self.pending.poll()
+    }
+
+    /// Wait for the command to finish and return its decoded payload.
+    ///
+    /// Args:
+    ///     timeout: Seconds to wait (None = until the command finishes)
+    ///
+    /// Raises:
+    ///     TimeoutError if the command is still running after `timeout` seconds
+    #[pyo3(signature = (timeout=None))]
+    fn result(&self, py: Python, timeout: Option<f64>) -> PyResult<Py<PyAny>> {
+        let timeout = duration_from_secs(timeout)?;
+        let pending = &self.pending;
+        match py.detach(|| pending.wait(timeout)) {
+            Some(result) => self.decoder.decode(py, &result?),
+            None => Err(PyErr::new::<pyo3::exceptions::PyTimeoutError, _>(
+                "command is still running",
+            )),
+        }
+    }
+
+    /// Abort the command if it hasn't completed yet.
+    fn cancel(&self) {
+        self.pending.cancel();
+    }
+
+    /// Waits on a helper thread and resolves an asyncio future with the result.
+    /// Cancelling the awaiting task cancels the command.
+    fn __await__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let pending = self.pending.clone();
+        let decoder = self.decoder;
+        let future = spawn_future(
+            py,
+            "elbo-command-await",
+            move |cancelled| loop {
+                if let Some(result) = pending.wait(Some(AWAIT_CANCEL_CHECK)) {
+                    return result;
+                }
+                if cancelled.load(Ordering::SeqCst) {
+                    pending.cancel();
+                    return Err(SdkError::Cancelled);
+                }
+            },
+            move |py, result: Result<EngineResponse, SdkError>| decoder.decode(py, &result?),
+        )?;
+        future.call_method0("__await__")
+    }
+}
@@ -0,0 +1,154 @@
+//! Per-call command options: timeouts and cooperative cancellation.
+//!
+//! Python code scopes options with `command_scope(timeout=..., cancel=...)`, which
+//! pushes them onto a thread-local stack read by `EngineClient::send_command`.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Shared flag that aborts a pending request once set
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandOptions {
+    /// Overrides the client's default timeout when set
+    pub timeout: Option<Duration>,
+    pub cancel: Option<CancelToken>,
+}
+
+thread_local! {
+    static SCOPED_OPTIONS: RefCell<Vec<CommandOptions>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Options of the innermost active `command_scope` on this thread
+pub fn scoped_options() -> CommandOptions {
+    SCOPED_OPTIONS.with(|stack| stack.borrow().last().cloned().unwrap_or_default())
+}
+
+/// Seconds from Python as a Duration; rejects negative, NaN and out-of-range values
+pub fn duration_from_secs(seconds: Option<f64>) -> PyResult<Option<Duration>> {
+    seconds
+        .map(|s| {
+            Duration::try_from_secs_f64(s).map_err(|_| {
+                PyValueError::new_err(format!(
+                    "timeout must be a non-negative number of seconds, got {}",
+                    s
+                ))
+            })
+        })
+        .transpose()
+}
+
+/// Deadlines further out than this are as good as never
+const FAR_FUTURE: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+/// `timeout` from now; a timeout too large to add to the clock waits (practically) forever
+pub fn deadline_after(timeout: Duration) -> Instant {
+    let now = Instant::now();
+    now.checked_add(timeout).unwrap_or_else(|| now + FAR_FUTURE)
+}
+
+/// Cancels engine commands issued inside a `command_scope` that was given this token.
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    pub token: CancelToken,
+}
+
+#[pymethods]
+impl CancellationToken {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Abort every pending command tied to this token.
+    fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Whether cancel() has been called.
+    #[getter]
+    fn cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
+
+/// Context manager applying a timeout and/or cancellation token to engine commands.
+///
+/// Args:
+///     timeout: Seconds to wait for each response (None = client default)
+///     cancel: Optional CancellationToken that aborts pending commands
+#[pyclass(unsendable)]
+pub struct CommandScope {
+    options: CommandOptions,
+}
+
+impl CommandScope {
+    pub fn new(options: CommandOptions) -> Self {
+        CommandScope { options }
+    }
+}
+
+#[pymethods]
+impl CommandScope {
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        let options = slf.options.clone();
+        SCOPED_OPTIONS.with(|stack| stack.borrow_mut().push(options));
+        slf
+    }
+
+    fn __exit__(
+        &self,
+        _exc_type: Option<Bound<'_, PyAny>>,
+        _exc_value: Option<Bound<'_, PyAny>>,
+        _traceback: Option<Bound<'_, PyAny>>,
+    ) -> bool {
+        SCOPED_OPTIONS.with(|stack| stack.borrow_mut().pop());
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeouts_outside_duration_range_are_rejected() {
+        assert!(duration_from_secs(Some(-1.0)).is_err());
+        assert!(duration_from_secs(Some(f64::NAN)).is_err());
+        assert!(duration_from_secs(Some(f64::INFINITY)).is_err());
+        assert!(duration_from_secs(Some(1e300)).is_err());
+        assert_eq!(
+            duration_from_secs(Some(1.5)).unwrap(),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(duration_from_secs(None).unwrap(), None);
+    }
+
+    #[test]
+    fn huge_timeouts_do_not_overflow_the_deadline() {
+        assert!(deadline_after(Duration::MAX) > Instant::now() + Duration::from_secs(3600));
+        let soon = deadline_after(Duration::from_secs(1));
+        assert!(soon <= Instant::now() + Duration::from_secs(1));
+    }
+}
@@ -1,83 +1,278 @@
 use iceoryx2::prelude::*;
 use pivot_com_types::{EngineCommand, EngineResponse};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use crossbeam::channel;
 
-const COMMAND_SERVICE_NAME: &str = "PivotEngine/CommandService";
-const COMMAND_EVENT_SERVICE_NAME: &str = "PivotEngine/CommandEvents";
+use crate::command_options::CancelToken;
+use crate::error::SdkError;
+use crate::service_names::ServiceNames;
+
+/// First pause between response checks after the last progress; doubles while nothing arrives
+const MIN_RESPONSE_BACKOFF: Duration = Duration::from_micros(50);
+/// Longest pause between response checks. Engines that signal responses wake us earlier;
+/// this also bounds how long newly queued work waits while commands are in flight
+const MAX_RESPONSE_BACKOFF: Duration = Duration::from_millis(10);
 
 pub struct CommandWork {
     pub cmd: EngineCommand,
     // A one-shot channel to send the response back to the caller
-    pub response_tx: channel::Sender<Result<EngineResponse, String>>,
+    pub response_tx: channel::Sender<Result<EngineResponse, SdkError>>,
+    pub limits: CommandLimits,
+    pub outstanding: Outstanding,
+}
+
+/// Counts a request from the moment it is queued until its caller has been answered
+#[derive(Debug)]
+pub struct Outstanding(Arc<AtomicUsize>);
+
+impl Outstanding {
+    pub fn new(counter: &Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Outstanding(counter.clone())
+    }
+}
+
+impl Drop for Outstanding {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// When the caller stops caring about a response
+#[derive(Debug, Clone, Default)]
+pub struct CommandLimits {
+    /// Point after which the request is abandoned, along with its timeout for reporting
+    pub deadline: Option<(Instant, Duration)>,
+    pub cancel: CancelToken,
+}
+
+impl CommandLimits {
+    /// Returns the error to report if the caller no longer wants this response
+    pub fn abandoned(&self) -> Option<SdkError> {
+        if self.cancel.is_cancelled() {
+            return Some(SdkError::Cancelled);
+        }
+        match self.deadline {
+            Some((deadline, timeout)) if Instant::now() >= deadline => {
+                Some(SdkError::Timeout(timeout))
+            }
+            _ => None,
+        }
+    }
+}
+
+type CommandPorts = (
+    iceoryx2::port::client::Client<ipc::Service, EngineCommand, (), EngineResponse, ()>,
+    iceoryx2::port::notifier::Notifier<ipc::Service>,
+);
+
+/// A request that has been published and is waiting for the engine's answer
+struct InFlight<P> {
+    pending: P,
+    response_tx: channel::Sender<Result<EngineResponse, SdkError>>,
+    limits: CommandLimits,
+    _outstanding: Outstanding,
 }
 
 pub fn spawn_command_thread(
     node: Arc<Node<ipc::Service>>,
+    names: Arc<ServiceNames>,
     command_rx: channel::Receiver<CommandWork>,
     shutdown: Arc<AtomicBool>,
+    max_in_flight: usize,
+    discovery_timeout: Duration,
+    ready_tx: channel::Sender<Result<(), SdkError>>,
 ) -> std::thread::JoinHandle<()> {
     thread::spawn(move || {
-
-        let (service, notifier) = loop {
-            let cmd_service = node
-                .service_builder(&COMMAND_SERVICE_NAME.try_into().unwrap())
-                .request_response::<EngineCommand, EngineResponse>()
-                .open();
-
-            let cmd_event_service = node
-                .service_builder(&COMMAND_EVENT_SERVICE_NAME.try_into().unwrap())
-                .event()
-                .open();
-
-            match (cmd_service, cmd_event_service) {
-                (Ok(s), Ok(n)) => break (s, n),
-                _ => {
-                    // Engine isn't fully ready yet, or services aren't registered.
-                    // Sleep for a bit and try again.
-                    thread::sleep(Duration::from_millis(500));
-                    println!("Waiting for Engine command services to appear...");
+        let (iox_client, cmd_notifier) =
+            match open_command_ports(&node, &names, &shutdown, discovery_timeout) {
+                Ok(ports) => {
+                    let _ = ready_tx.send(Ok(()));
+                    ports
                 }
-            }
-        };
+                Err(e) => {
+                    log::error!("Command service unavailable: {}", e);
+                    let _ = ready_tx.send(Err(e.clone()));
+                    // Keep answering callers so nobody blocks on a thread that will never serve them
+                    while !shutdown.load(Ordering::Relaxed) {
+                        if let Ok(work) = command_rx.recv_timeout(Duration::from_millis(200)) {
+                            let _ = work.response_tx.send(Err(e.clone()));
+                        }
+                    }
+                    return;
+                }
+            };
+        // Engines that signal finished responses let us sleep until one arrives instead of polling
+        let response_listener = open_response_listener(&node, &names);
+        let max_in_flight = max_in_flight.max(1);
 
-        println!("Command service loop active.");
+        log::debug!("Command service loop active");
 
-        let iox_client = service.client_builder().create().unwrap();
-        let cmd_notifier = notifier
-            .notifier_builder()
-            .create()
-            .expect("Failed to create Notifier");
+        let mut in_flight = Vec::with_capacity(max_in_flight);
+        let mut backoff = MIN_RESPONSE_BACKOFF;
 
         while !shutdown.load(Ordering::Relaxed) {
-            while let Ok(work) = command_rx.recv_timeout(Duration::from_millis(200)) {
-                let result = (|| -> Result<EngineResponse, String> {
+            let mut progressed = false;
+
+            // Top up the window with queued work, only blocking when nothing is outstanding
+            while in_flight.len() < max_in_flight {
+                let next = if in_flight.is_empty() {
+                    command_rx.recv_timeout(Duration::from_millis(200)).ok()
+                } else {
+                    command_rx.try_recv().ok()
+                };
+                let Some(CommandWork {
+                    cmd,
+                    response_tx,
+                    limits,
+                    outstanding,
+                }) = next
+                else {
+                    break;
+                };
+
+                // Skip requests whose caller gave up while they were queued
+                if let Some(e) = limits.abandoned() {
+                    let _ = response_tx.send(Err(e));
+                    continue;
+                }
+
+                let sent = (|| -> Result<_, SdkError> {
                     let request = iox_client
                         .loan_uninit()
-                        .map_err(|e| format!("SHM loan failed: {}", e))?;
+                        .map_err(|e| SdkError::ShmLoan(e.to_string()))?;
                     let pending = request
-                        .write_payload(work.cmd)
+                        .write_payload(cmd)
                         .send()
-                        .map_err(|e| format!("Send failed: {}", e))?;
+                        .map_err(|e| SdkError::ShmSend(e.to_string()))?;
                     // Notify the engine that a new command is available
                     cmd_notifier
                         .notify()
-                        .map_err(|e| format!("Notifier failed: {}", e))?;
-                    loop {
-                        if let Some(res) = pending.receive().map_err(|e| e.to_string())? {
-                            return Ok(res.payload().clone());
-                        }
-                        // Tiny sleep to prevent 100% CPU during the microsecond wait
-                        thread::sleep(std::time::Duration::from_micros(100));
-                    }
+                        .map_err(|e| SdkError::Transport(format!("Notifier failed: {}", e)))?;
+                    Ok(pending)
                 })();
 
-                let _ = work.response_tx.send(result);
+                progressed = true;
+                match sent {
+                    Ok(pending) => in_flight.push(InFlight {
+                        pending,
+                        response_tx,
+                        limits,
+                        _outstanding: outstanding,
+                    }),
+                    Err(e) => {
+                        let _ = response_tx.send(Err(e));
+                    }
+                }
             }
+
+            if in_flight.is_empty() {
+                continue;
+            }
+
+            // Hand every finished response back to its caller; dropping a PendingResponse
+            // tells iceoryx2 we no longer expect an answer for it
+            in_flight.retain(|req| {
+                let result = match req.pending.receive() {
+                    Ok(Some(res)) => Ok(res.payload().clone()),
+                    Ok(None) => match req.limits.abandoned() {
+                        Some(e) => Err(e),
+                        None => return true,
+                    },
+                    Err(e) => Err(SdkError::Transport(e.to_string())),
+                };
+                let _ = req.response_tx.send(result);
+                progressed = true;
+                false
+            });
+
+            if !in_flight.is_empty() {
+                // Stay responsive while responses keep coming, back off while the engine is busy
+                backoff = if progressed {
+                    MIN_RESPONSE_BACKOFF
+                } else {
+                    (backoff * 2).min(MAX_RESPONSE_BACKOFF)
+                };
+                match &response_listener {
+                    Some(listener) => {
+                        let _ = listener.timed_wait_all(|_| {}, backoff);
+                    }
+                    None => thread::sleep(backoff),
+                }
+            }
+        }
+
+        for req in in_flight {
+            let _ = req.response_tx.send(Err(SdkError::Transport(
+                "Command thread shut down before the engine responded".to_string(),
+            )));
         }
-        println!("Command service loop exiting.");
+        log::debug!("Command service loop exiting");
     })
 }
+
+/// Opens the optional event service the engine notifies after writing a response
+fn open_response_listener(
+    node: &Node<ipc::Service>,
+    names: &ServiceNames,
+) -> Option<iceoryx2::port::listener::Listener<ipc::Service>> {
+    let name: ServiceName = names.command_responses.as_str().try_into().ok()?;
+    let service = node.service_builder(&name).event().open().ok()?;
+    service.listener_builder().create().ok()
+}
+
+/// Waits for the engine to register its command services and creates this thread's ports
+fn open_command_ports(
+    node: &Node<ipc::Service>,
+    names: &ServiceNames,
+    shutdown: &AtomicBool,
+    discovery_timeout: Duration,
+) -> Result<CommandPorts, SdkError> {
+    let service_name: ServiceName = names
+        .command
+        .as_str()
+        .try_into()
+        .map_err(|e| SdkError::Ipc(format!("{:?}", e)))?;
+    let event_name: ServiceName = names
+        .command_events
+        .as_str()
+        .try_into()
+        .map_err(|e| SdkError::Ipc(format!("{:?}", e)))?;
+    let deadline = Instant::now() + discovery_timeout;
+
+    let (service, notifier) = loop {
+        let cmd_service = node
+            .service_builder(&service_name)
+            .request_response::<EngineCommand, EngineResponse>()
+            .open();
+
+        let cmd_event_service = node.service_builder(&event_name).event().open();
+
+        match (cmd_service, cmd_event_service) {
+            (Ok(s), Ok(n)) => break (s, n),
+            _ => {
+                if shutdown.load(Ordering::Relaxed) || Instant::now() >= deadline {
+                    return Err(SdkError::ServiceDiscoveryTimeout(names.command.clone()));
+                }
+                // Engine isn't fully ready yet, or services aren't registered.
+                // Sleep for a bit and try again.
+                thread::sleep(Duration::from_millis(500));
+                log::debug!("Waiting for engine command services to appear");
+            }
+        }
+    };
+
+    let iox_client = service
+        .client_builder()
+        .create()
+        .map_err(|e| SdkError::Ipc(format!("Failed to create client: {:?}", e)))?;
+    let cmd_notifier = notifier
+        .notifier_builder()
+        .create()
+        .map_err(|e| SdkError::Ipc(format!("Failed to create Notifier: {:?}", e)))?;
+
+    Ok((iox_client, cmd_notifier))
+}
@@ -0,0 +1,502 @@
+//! Engine instances.
+//!
+//! The module-level functions forward to the methods here on one default engine. An `Engine`
+//! owns its own client, worker threads and service namespace, so several engines (e.g.
+//! preview and batch export) can run side by side in one process.
+
+use pivot_com_types::fields::Uuid;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::asset_sync_context::AssetSyncContext;
+use crate::command_handle::{CommandHandle, ResponseDecoder};
+use crate::command_options::duration_from_secs;
+use crate::engine_api;
+use crate::engine_client::{DEFAULT_START_TIMEOUT, DEFAULT_STOP_TIMEOUT, EngineClient};
+use crate::engine_launch::EngineLaunchConfig;
+use crate::engine_logs::DEFAULT_TAIL_LINES;
+use crate::error::SdkError;
+use crate::mesh_batch_builder::extract_array;
+use crate::mesh_queue::{
+    DEFAULT_MESH_QUEUE_CAPACITY, MeshQueueConfig, MeshQueuePolicy, MeshQueueStats,
+};
+use crate::mesh_subscription::{self, MeshSubscription, MeshUpdates};
+use crate::sdk_config::SdkConfig;
+use crate::service_names::DEFAULT_SERVICE_PREFIX;
+use crate::supervisor::{
+    DEFAULT_INITIAL_BACKOFF, DEFAULT_MAX_BACKOFF, DEFAULT_MAX_RESTARTS, EngineState, RestartPolicy,
+    StateCallback, StopOutcome,
+};
+
+/// An independent engine with its own process, threads and iceoryx2 services.
+///
+/// Args:
+///     namespace: Prefix of the engine's services (default: config.service_prefix if a
+///         config is given, otherwise a unique one per instance)
+///     config: Optional SdkConfig (node name, iceoryx2 config file, domain)
+#[pyclass(frozen)]
+pub struct Engine {
+    pub client: Arc<EngineClient>,
+}
+
+impl Engine {
+    pub fn from_client(client: Arc<EngineClient>) -> Self {
+        Engine { client }
+    }
+}
+
+/// Wraps a Python `callback(state, detail)` so it can be called from any thread
+pub fn py_state_callback(callback: Py<PyAny>) -> StateCallback {
+    Arc::new(move |state: EngineState, detail: Option<&str>| {
+        Python::attach(|py| {
+            if let Err(e) = callback.call1(py, (state, detail)) {
+                e.print(py);
+            }
+        });
+    })
+}
+
+/// A service prefix no other Engine in this or another process is using
+fn unique_namespace() -> String {
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    format!("{}_{}", DEFAULT_SERVICE_PREFIX, &id[..12])
+}
+
+#[pymethods]
+impl Engine {
+    #[new]
+    #[pyo3(signature = (namespace=None, config=None))]
+    fn new(namespace: Option<String>, config: Option<SdkConfig>) -> PyResult<Self> {
+        let mut config = match config {
+            Some(config) => config,
+            None => SdkConfig {
+                service_prefix: unique_namespace(),
+                ..SdkConfig::default()
+            },
+        };
+        if let Some(namespace) = namespace {
+            config.service_prefix = namespace;
+        }
+        if config.service_prefix.is_empty() {
+            return Err(SdkError::Other("Engine namespace must not be empty".to_string()).into());
+        }
+        Ok(Engine::from_client(engine_api::new_client(config)))
+    }
+
+    /// Prefix of this engine's iceoryx2 services
+    #[getter]
+    fn namespace(&self) -> String {
+        self.client.service_names().prefix
+    }
+
+    #[getter]
+    pub(crate) fn config(&self) -> SdkConfig {
+        engine_api::sdk_config(&self.client)
+    }
+
+    /// Replaces the SDK config; only allowed while the engine is stopped.
+    pub(crate) fn set_config(&self, config: SdkConfig) -> PyResult<()> {
+        engine_api::set_sdk_config(&self.client, config)?;
+        Ok(())
+    }
+
+    /// Launches this engine and blocks until it is ready. See start_engine().
+    #[pyo3(signature = (timeout=None, config=None))]
+    pub(crate) fn start(
+        &self,
+        py: Python,
+        timeout: Option<f64>,
+        config: Option<EngineLaunchConfig>,
+    ) -> PyResult<()> {
+        let timeout = duration_from_secs(timeout)?.unwrap_or(DEFAULT_START_TIMEOUT);
+        py.detach(|| engine_api::start_engine(&self.client, config.unwrap_or_default(), timeout))?;
+        Ok(())
+    }
+
+    /// Attaches to an engine already serving this namespace. See connect_engine().
+    #[pyo3(signature = (timeout=None))]
+    pub(crate) fn connect(&self, py: Python, timeout: Option<f64>) -> PyResult<()> {
+        let timeout = duration_from_secs(timeout)?.unwrap_or(DEFAULT_START_TIMEOUT);
+        py.detach(|| engine_api::connect_engine(&self.client, timeout))?;
+        Ok(())
+    }
+
+    /// Drains, stops and if necessary kills this engine. See stop_engine().
+    #[pyo3(signature = (timeout=None, drain=true))]
+    pub(crate) fn stop(
+        &self,
+        py: Python,
+        timeout: Option<f64>,
+        drain: bool,
+    ) -> PyResult<StopOutcome> {
+        let timeout = duration_from_secs(timeout)?.unwrap_or(DEFAULT_STOP_TIMEOUT);
+        // State callbacks fire on other threads during the stop and need the GIL
+        let outcome = py.detach(|| engine_api::stop_engine(&self.client, timeout, drain))?;
+        Ok(outcome)
+    }
+
+    pub(crate) fn owns_engine(&self) -> Option<bool> {
+        engine_api::owns_engine(&self.client)
+    }
+
+    #[getter]
+    pub(crate) fn state(&self) -> EngineState {
+        engine_api::engine_state(&self.client)
+    }
+
+    #[getter]
+    pub(crate) fn version(&self) -> Option<String> {
+        engine_api::engine_info(&self.client).map(|info| info.version)
+    }
+
+    #[getter]
+    pub(crate) fn capabilities(&self) -> u64 {
+        engine_api::engine_info(&self.client).map_or(0, |info| info.capabilities)
+    }
+
+    #[pyo3(signature = (n=DEFAULT_TAIL_LINES))]
+    pub(crate) fn logs(&self, n: usize) -> Vec<String> {
+        engine_api::engine_logs(&self.client, n)
+    }
+
+    #[pyo3(signature = (callback=None))]
+    pub(crate) fn set_state_callback(&self, callback: Option<Py<PyAny>>) {
+        engine_api::set_engine_state_callback(&self.client, callback.map(py_state_callback));
+    }
+
+    #[pyo3(signature = (
+        max_restarts=DEFAULT_MAX_RESTARTS,
+        initial_backoff=DEFAULT_INITIAL_BACKOFF.as_secs_f64(),
+        max_backoff=DEFAULT_MAX_BACKOFF.as_secs_f64(),
+    ))]
+    pub(crate) fn set_restart_policy(
+        &self,
+        max_restarts: u32,
+        initial_backoff: f64,
+        max_backoff: f64,
+    ) -> PyResult<()> {
+        let policy = RestartPolicy {
+            max_restarts,
+            initial_backoff: duration_from_secs(Some(initial_backoff))?.unwrap_or_default(),
+            max_backoff: duration_from_secs(Some(max_backoff))?.unwrap_or_default(),
+        };
+        engine_api::set_restart_policy(&self.client, policy);
+        Ok(())
+    }
+
+    #[pyo3(signature = (seconds=None))]
+    pub(crate) fn set_command_timeout(&self, seconds: Option<f64>) -> PyResult<()> {
+        engine_api::set_command_timeout(&self.client, duration_from_secs(seconds)?);
+        Ok(())
+    }
+
+    pub(crate) fn get_command_timeout(&self) -> Option<f64> {
+        engine_api::command_timeout(&self.client).map(|t| t.as_secs_f64())
+    }
+
+    pub(crate) fn set_max_in_flight(&self, max_in_flight: usize) {
+        engine_api::set_max_in_flight(&self.client, max_in_flight);
+    }
+
+    #[pyo3(signature = (capacity=DEFAULT_MESH_QUEUE_CAPACITY, policy=MeshQueuePolicy::Block))]
+    pub(crate) fn set_mesh_queue(&self, capacity: usize, policy: MeshQueuePolicy) -> PyResult<()> {
+        engine_api::set_mesh_queue(&self.client, MeshQueueConfig { capacity, policy })?;
+        Ok(())
+    }
+
+    pub(crate) fn mesh_queue_stats(&self) -> MeshQueueStats {
+        engine_api::mesh_queue_stats(&self.client)
+    }
+
+    pub(crate) fn poll_mesh_sync(&self, py: Python) -> PyResult<Option<AssetSyncContext>> {
+        Ok(py.detach(|| engine_api::poll_mesh_sync(&self.client))?)
+    }
+
+    pub(crate) fn send_transforms(
+        &self,
+        py: Python,
+        uuids: Vec<Uuid>,
+        matrices: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        let matrices = extract_array::<f32>(matrices, "matrices")?;
+        py.detach(|| engine_api::send_transforms_command(&self.client, uuids, matrices))?;
+        Ok(())
+    }
+
+    #[pyo3(signature = (max=None))]
+    pub(crate) fn poll_mesh_sync_all(
+        &self,
+        py: Python,
+        max: Option<usize>,
+    ) -> PyResult<Option<AssetSyncContext>> {
+        Ok(py.detach(|| engine_api::poll_mesh_sync_all(&self.client, max))?)
+    }
+
+    #[pyo3(signature = (timeout=None))]
+    pub(crate) fn wait_mesh_sync(
+        &self,
+        py: Python,
+        timeout: Option<f64>,
+    ) -> PyResult<Option<AssetSyncContext>> {
+        mesh_subscription::wait_for_update(py, &self.client, duration_from_secs(timeout)?)
+    }
+
+    pub(crate) fn subscribe_mesh_updates(&self, callback: Py<PyAny>) -> PyResult<MeshSubscription> {
+        MeshSubscription::start(self.client.clone(), callback)
+    }
+
+    #[pyo3(signature = (timeout=None))]
+    pub(crate) fn mesh_updates(&self, timeout: Option<f64>) -> PyResult<MeshUpdates> {
+        Ok(MeshUpdates::new(
+            self.client.clone(),
+            duration_from_secs(timeout)?,
+        ))
+    }
+
+    pub(crate) fn prepare_mesh_send(
+        &self,
+        py: Python,
+        vert_counts: Vec<u32>,
+        edge_counts: Vec<u32>,
+        loop_counts: Vec<u32>,
+        total_loop_lengths: Vec<u32>,
+        object_counts: Vec<u32>,
+        group_names: Vec<String>,
+        surface_contexts: Vec<u16>,
+        asset_uuids: Vec<Uuid>,
+    ) -> PyResult<AssetSyncContext> {
+        let context = py.detach(|| {
+            engine_api::allocate_memory(
+                &self.client,
+                vert_counts,
+                edge_counts,
+                loop_counts,
+                total_loop_lengths,
+                object_counts,
+                group_names,
+                surface_contexts,
+                asset_uuids,
+            )
+        })?;
+        Ok(context)
+    }
+
+    pub(crate) fn standardize_groups_command(&self, py: Python, uuids: Vec<Uuid>) -> PyResult<()> {
+        py.detach(|| engine_api::standardize_groups_command(&self.client, uuids))?;
+        Ok(())
+    }
+
+    pub(crate) fn standardize_synced_groups_command(
+        &self,
+        py: Python,
+        uuids: Vec<Uuid>,
+        surface_contexts: Vec<u32>,
+    ) -> PyResult<()> {
+        py.detach(|| {
+            engine_api::standardize_synced_groups_command(&self.client, uuids, surface_contexts)
+        })?;
+        Ok(())
+    }
+
+    pub(crate) fn set_surface_types_command(
+        &self,
+        py: Python,
+        group_surface_map: HashMap<Uuid, i64>,
+    ) -> PyResult<()> {
+        py.detach(|| engine_api::set_surface_types_command(&self.client, group_surface_map))?;
+        Ok(())
+    }
+
+    pub(crate) fn get_surface_types_command(&self, py: Python) -> PyResult<HashMap<Vec<u8>, u64>> {
+        let surfaces = py.detach(|| engine_api::get_surface_types_command(&self.client))?;
+        Ok(surfaces
+            .into_iter()
+            .map(|(uuid, surface_type)| (uuid.bytes.to_vec(), surface_type))
+            .collect())
+    }
+
+    pub(crate) fn drop_groups_command(&self, py: Python, uuids: Vec<Uuid>) -> PyResult<()> {
+        py.detach(|| engine_api::drop_groups_command(&self.client, uuids))?;
+        Ok(())
+    }
+
+    pub(crate) fn drop_all_groups_command(&self, py: Python) -> PyResult<()> {
+        py.detach(|| engine_api::drop_all_groups_command(&self.client))?;
+        Ok(())
+    }
+
+    pub(crate) fn organize_objects_command(&self, py: Python) -> PyResult<()> {
+        py.detach(|| engine_api::organize_objects_command(&self.client))?;
+        Ok(())
+    }
+
+    pub(crate) fn group_all_objects_command(&self, py: Python) -> PyResult<()> {
+        py.detach(|| engine_api::group_all_objects_command(&self.client))?;
+        Ok(())
+    }
+
+    pub(crate) fn extract_geometric_features_command(
+        &self,
+        py: Python,
+        uuids: Vec<Uuid>,
+    ) -> PyResult<()> {
+        py.detach(|| engine_api::extract_geometric_features_command(&self.client, uuids))?;
+        Ok(())
+    }
+
+    pub(crate) fn embed_all_assets_command(&self, py: Python) -> PyResult<()> {
+        py.detach(|| engine_api::embed_all_assets_command(&self.client))?;
+        Ok(())
+    }
+
+    pub(crate) fn import_assets_command(&self, py: Python, paths: Vec<String>) -> PyResult<()> {
+        py.detach(|| engine_api::import_assets_command(&self.client, paths))?;
+        Ok(())
+    }
+
+    pub(crate) fn export_assets_command(
+        &self,
+        py: Python,
+        path: String,
+        target_bytes: u64,
+        uuids: Vec<Uuid>,
+    ) -> PyResult<()> {
+        py.detach(|| engine_api::export_assets_command(&self.client, &path, target_bytes, uuids))?;
+        Ok(())
+    }
+
+    pub(crate) fn export_all_command(
+        &self,
+        py: Python,
+        path: String,
+        target_bytes: u64,
+    ) -> PyResult<()> {
+        py.detach(|| engine_api::export_all_command(&self.client, &path, target_bytes))?;
+        Ok(())
+    }
+
+    pub(crate) fn export_mesh_tbo_command(
+        &self,
+        py: Python,
+        path: String,
+        target_bytes: u64,
+        flags: u32,
+        uuids: Vec<Uuid>,
+    ) -> PyResult<()> {
+        py.detach(|| {
+            engine_api::export_mesh_tbo_command(&self.client, &path, target_bytes, flags, uuids)
+        })?;
+        Ok(())
+    }
+
+    pub(crate) fn export_asset_tbo_command(
+        &self,
+        py: Python,
+        path: String,
+        target_bytes: u64,
+        uuids: Vec<Uuid>,
+    ) -> PyResult<()> {
+        py.detach(|| {
+            engine_api::export_asset_tbo_command(&self.client, &path, target_bytes, uuids)
+        })?;
+        Ok(())
+    }
+
+    pub(crate) fn export_all_asset_tbo_command(
+        &self,
+        py: Python,
+        path: String,
+        target_bytes: u64,
+    ) -> PyResult<Vec<String>> {
+        let resp = py.detach(|| {
+            engine_api::export_all_asset_tbo_command(&self.client, &path, target_bytes)
+        })?;
+        let filenames = resp
+            .read_tbo_flush()
+            .map_err(|e| SdkError::Decode(format!("Failed to read flush response: {}", e)))?;
+        Ok(filenames.into_iter().map(|s| s.to_string()).collect())
+    }
+
+    pub(crate) fn export_all_tbo_command(
+        &self,
+        py: Python,
+        path: String,
+        target_bytes: u64,
+        flags: u32,
+        target_point_count: u32,
+    ) -> PyResult<()> {
+        py.detach(|| {
+            engine_api::export_all_tbo_command(
+                &self.client,
+                &path,
+                target_bytes,
+                flags,
+                target_point_count,
+            )
+        })?;
+        Ok(())
+    }
+
+    pub(crate) fn organize_objects_command_async(&self) -> PyResult<CommandHandle> {
+        let pending = engine_api::organize_objects_command_async(&self.client)?;
+        Ok(CommandHandle::new(pending, ResponseDecoder::Unit))
+    }
+
+    pub(crate) fn import_assets_command_async(
+        &self,
+        paths: Vec<String>,
+    ) -> PyResult<CommandHandle> {
+        let pending = engine_api::import_assets_command_async(&self.client, paths)?;
+        Ok(CommandHandle::new(pending, ResponseDecoder::Unit))
+    }
+
+    pub(crate) fn export_all_command_async(
+        &self,
+        path: String,
+        target_bytes: u64,
+    ) -> PyResult<CommandHandle> {
+        let pending = engine_api::export_all_command_async(&self.client, &path, target_bytes)?;
+        Ok(CommandHandle::new(pending, ResponseDecoder::Unit))
+    }
+
+    pub(crate) fn export_all_asset_tbo_command_async(
+        &self,
+        path: String,
+        target_bytes: u64,
+    ) -> PyResult<CommandHandle> {
+        let pending =
+            engine_api::export_all_asset_tbo_command_async(&self.client, &path, target_bytes)?;
+        Ok(CommandHandle::new(pending, ResponseDecoder::FileList))
+    }
+
+    pub(crate) fn export_all_tbo_command_async(
+        &self,
+        path: String,
+        target_bytes: u64,
+        flags: u32,
+        target_point_count: u32,
+    ) -> PyResult<CommandHandle> {
+        let pending = engine_api::export_all_tbo_command_async(
+            &self.client,
+            &path,
+            target_bytes,
+            flags,
+            target_point_count,
+        )?;
+        Ok(CommandHandle::new(pending, ResponseDecoder::Unit))
+    }
+
+    pub(crate) fn embed_all_assets_command_async(&self) -> PyResult<CommandHandle> {
+        let pending = engine_api::embed_all_assets_command_async(&self.client)?;
+        Ok(CommandHandle::new(pending, ResponseDecoder::Unit))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Engine(namespace={:?}, state={:?})",
+            self.client.service_names().prefix,
+            self.client.engine_state()
+        )
+    }
+}
@@ -1,36 +1,139 @@
 use pivot_com_types::EngineCommand;
 use pivot_com_types::EngineResponse;
+use pivot_com_types::MeshPublish;
 use pivot_com_types::asset_meta::AssetMeta;
 use pivot_com_types::asset_ptr::AssetPtr;
 use pivot_com_types::asset_surface::GroupSurface;
 use pivot_com_types::fields::Uuid;
 
 use crate::asset_sync_context::AssetSyncContext;
-use crate::engine_client::EngineClient;
-use std::collections::HashMap;
+use crate::engine_client::{EngineClient, PendingCommand};
+use crate::engine_launch::EngineLaunchConfig;
+use crate::error::SdkError;
+use crate::mesh_queue::{MeshQueueConfig, MeshQueueStats};
+use crate::protocol::{CAP_TRANSFORM_UPDATES, EngineInfo};
+use crate::sdk_config::SdkConfig;
+use crate::supervisor::{EngineState, RestartPolicy, StateCallback, StopOutcome};
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
 use std::iter::zip;
+#[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 use std::path::PathBuf;
-use std::sync::{LazyLock, Mutex};
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+use std::time::Duration;
 use uuid::Uuid as ExternalUuid;
 
-pub static CLIENT: LazyLock<EngineClient> = LazyLock::new(|| EngineClient::new());
+pub static CLIENT: LazyLock<Arc<EngineClient>> = LazyLock::new(|| new_client(SdkConfig::default()));
+/// Every client created in this process, so their engines can be stopped at interpreter exit
+static CLIENTS: LazyLock<Mutex<Vec<Weak<EngineClient>>>> = LazyLock::new(|| Mutex::new(Vec::new()));
 pub static ENGINE_DIR: LazyLock<Mutex<Option<PathBuf>>> = LazyLock::new(|| Mutex::new(None));
 
-pub fn start_engine() -> Result<(), String> {
-    let engine_path = resolve_engine_binary_path()
-        .ok_or_else(|| "Failed to locate pivot_engine binary".to_string())?;
-    CLIENT.start(engine_path.to_string_lossy().to_string())?;
+pub fn new_client(config: SdkConfig) -> Arc<EngineClient> {
+    let client = Arc::new(EngineClient::with_config(config));
+    let mut clients = CLIENTS.lock().unwrap();
+    clients.retain(|c| c.strong_count() > 0);
+    clients.push(Arc::downgrade(&client));
+    client
+}
+
+pub fn start_engine(
+    client: &Arc<EngineClient>,
+    config: EngineLaunchConfig,
+    timeout: Duration,
+) -> Result<(), SdkError> {
+    let engine_path = resolve_engine_binary_path().ok_or_else(|| {
+        SdkError::ProcessSpawn("Failed to locate pivot_engine binary".to_string())
+    })?;
+    client.start(engine_path.to_string_lossy().to_string(), config, timeout)?;
     Ok(())
 }
 
-pub fn stop_engine() -> Result<(), String> {
-    CLIENT.stop()?;
+pub fn connect_engine(client: &Arc<EngineClient>, timeout: Duration) -> Result<(), SdkError> {
+    client.connect(timeout)
+}
+
+pub fn owns_engine(client: &Arc<EngineClient>) -> Option<bool> {
+    client.owns_engine()
+}
+
+pub fn stop_engine(
+    client: &Arc<EngineClient>,
+    timeout: Duration,
+    drain: bool,
+) -> Result<StopOutcome, SdkError> {
+    client.stop(timeout, drain)
+}
+
+/// Stops every engine this process started; registered with atexit so none outlives the interpreter
+pub fn stop_all_engines(timeout: Duration) {
+    let clients: Vec<Arc<EngineClient>> = CLIENTS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect();
+
+    for client in clients {
+        if let Err(e) = client.stop(timeout, false) {
+            log::warn!("Failed to stop engine at exit: {}", e);
+        }
+    }
+}
+
+pub fn sdk_config(client: &Arc<EngineClient>) -> SdkConfig {
+    client.config()
+}
+
+pub fn set_sdk_config(client: &Arc<EngineClient>, config: SdkConfig) -> Result<(), SdkError> {
+    client.set_config(config)
+}
+
+pub fn set_command_timeout(client: &Arc<EngineClient>, timeout: Option<Duration>) {
+    client.set_default_timeout(timeout);
+}
+
+pub fn command_timeout(client: &Arc<EngineClient>) -> Option<Duration> {
+    client.default_timeout()
+}
+
+pub fn set_max_in_flight(client: &Arc<EngineClient>, max_in_flight: usize) {
+    client.set_max_in_flight(max_in_flight);
+}
+
+pub fn set_mesh_queue(client: &Arc<EngineClient>, config: MeshQueueConfig) -> Result<(), SdkError> {
+    if config.capacity == 0 {
+        return Err(SdkError::Other("Mesh queue capacity must be at least 1".to_string()));
+    }
+    client.set_mesh_queue(config);
     Ok(())
 }
 
+pub fn mesh_queue_stats(client: &Arc<EngineClient>) -> MeshQueueStats {
+    client.mesh_queue_stats()
+}
+
+pub fn engine_state(client: &Arc<EngineClient>) -> EngineState {
+    client.engine_state()
+}
+
+pub fn set_engine_state_callback(client: &Arc<EngineClient>, callback: Option<StateCallback>) {
+    client.set_state_callback(callback);
+}
+
+pub fn set_restart_policy(client: &Arc<EngineClient>, policy: RestartPolicy) {
+    client.set_restart_policy(policy);
+}
+
+pub fn engine_info(client: &Arc<EngineClient>) -> Option<EngineInfo> {
+    client.engine_info()
+}
+
+pub fn engine_logs(client: &Arc<EngineClient>, n: usize) -> Vec<String> {
+    client.engine_logs(n)
+}
+
 pub fn get_uuid_size() -> usize {
     std::mem::size_of::<Uuid>()
 }
@@ -44,22 +147,98 @@ pub fn generate_uuid_bytes() -> [u8; Uuid::SIZE] {
     bytes
 }
 
-pub fn poll_mesh_sync() -> Result<Option<AssetSyncContext>, String> {
-    let mp = match CLIENT.poll_mesh_sync() {
+pub fn poll_mesh_sync(client: &Arc<EngineClient>) -> Result<Option<AssetSyncContext>, SdkError> {
+    let mp = match client.poll_mesh_sync() {
         Ok(Some(mp)) => mp,
         Ok(None) => return Ok(None),
-        Err(e) => return Err(e),
+        Err(e) => return Err(e.into()),
     };
 
+    context_from_publish(client, &mp).map(Some)
+}
+
+/// Drains up to `max` queued mesh updates into one read-only context. An asset sent
+/// several times appears once, with its newest copy
+pub fn poll_mesh_sync_all(
+    client: &Arc<EngineClient>,
+    max: Option<usize>,
+) -> Result<Option<AssetSyncContext>, SdkError> {
+    let publishes = client.drain_mesh_sync(max);
+    let Some(latest) = publishes.last() else {
+        return Ok(None);
+    };
+    let root_handle = latest.header.root_slab_handle;
+    let engine = client.engine_info();
+
+    // Updates from before the engine recreated its memory point into slabs that are gone
+    let current: Vec<&MeshPublish> = publishes
+        .iter()
+        .filter(|mp| mp.header.root_slab_handle == root_handle)
+        .collect();
+    let transforms_only = current
+        .iter()
+        .all(|mp| engine.as_ref().is_some_and(|info| info.is_transform_update(mp)));
+
+    let mut asset_ptrs: Vec<AssetPtr> = Vec::new();
+    for mp in current {
+        let ptrs = mp.read_send_mesh()
+            .map_err(|e| SdkError::Decode(e.to_string()))?;
+        asset_ptrs.extend_from_slice(ptrs);
+    }
+    let (ptrs, slab_refs) = client.hydrate_ptrs(&asset_ptrs, &root_handle, None)?;
+
+    // Keep the last copy of every UUID, in the order those copies arrived
+    let mut seen = HashSet::new();
+    let mut keep: Vec<usize> = (0..ptrs.len())
+        .rev()
+        .filter(|&i| seen.insert(unsafe { ptrs[i].as_ref().uuid.bytes }))
+        .collect();
+    keep.reverse();
+
+    let kept_ptrs: Vec<AssetPtr> = keep.iter().map(|&i| asset_ptrs[i]).collect();
+    let mut context = AssetSyncContext::new(
+        client.clone(),
+        keep.iter().map(|&i| ptrs[i]).collect(),
+        keep.iter().map(|&i| slab_refs[i].clone()).collect(),
+        &kept_ptrs,
+        true,
+    )?;
+    context.set_transforms_only(transforms_only);
+    Ok(Some(context))
+}
+
+/// Blocks up to `timeout` for the next mesh update
+pub fn wait_mesh_sync(
+    client: &Arc<EngineClient>,
+    timeout: Duration,
+) -> Result<Option<AssetSyncContext>, SdkError> {
+    match client.wait_mesh_sync(timeout)? {
+        Some(mp) => context_from_publish(client, &mp).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Maps the memory a mesh publish points at into a read-only context
+pub fn context_from_publish(
+    client: &Arc<EngineClient>,
+    mp: &MeshPublish,
+) -> Result<AssetSyncContext, SdkError> {
     let asset_ptrs = mp.read_send_mesh()
-        .map_err(|e| format!("Buffer read error: {}", e))?;
-    let ptrs = CLIENT.hydrate_ptrs(asset_ptrs, &mp.header.root_slab_handle)?;
+        .map_err(|e| SdkError::Decode(e.to_string()))?;
+    let (ptrs, slab_refs) = client.hydrate_ptrs(asset_ptrs, &mp.header.root_slab_handle, None)?;
 
-    Ok(Some(AssetSyncContext::new(ptrs, asset_ptrs)))
+    let mut context = AssetSyncContext::new(client.clone(), ptrs, slab_refs, asset_ptrs, true)?;
+    context.set_transforms_only(
+        client
+            .engine_info()
+            .is_some_and(|info| info.is_transform_update(mp)),
+    );
+    Ok(context)
 }
 
 /// Requests memory for the provided asset metadata and writes the group names and asset metas into the correct places
 pub fn allocate_memory(
+    client: &Arc<EngineClient>,
     vert_counts: Vec<u32>,
     edge_counts: Vec<u32>,
     loop_counts: Vec<u32>,
@@ -68,9 +247,25 @@ pub fn allocate_memory(
     group_names: Vec<String>,
     surface_contexts: Vec<u16>,
     asset_uuids: Vec<Uuid>,
-) -> Result<AssetSyncContext, String> {
+) -> Result<AssetSyncContext, SdkError> {
     let count = asset_uuids.len();
 
+    let lengths = [
+        vert_counts.len(),
+        edge_counts.len(),
+        loop_counts.len(),
+        total_loop_lengths.len(),
+        object_counts.len(),
+        group_names.len(),
+        surface_contexts.len(),
+    ];
+    if lengths.iter().any(|&len| len != count) {
+        return Err(SdkError::Other(format!(
+            "All per-asset lists must have {} entries, got {:?}",
+            count, lengths
+        )));
+    }
+
     let mut sizes = Vec::with_capacity(count);
     let mut asset_metas = Vec::with_capacity(count);
 
@@ -92,13 +287,15 @@ pub fn allocate_memory(
     }
 
     let command = EngineCommand::alloc_request(&asset_uuids, &sizes);
-    let resp = CLIENT.send_command(command)?;
+    let resp = client.send_command(command)?;
 
     let (_uuids, asset_ptrs) = resp
         .read_alloc_response()
-        .map_err(|e| format!("Buffer read error: {}", e))?;
+        .map_err(|e| SdkError::Decode(e.to_string()))?;
 
-    let ptrs = CLIENT.hydrate_ptrs(asset_ptrs, &resp.header.root_slab_handle)?;
+    let alloc_sizes: Vec<u64> = sizes.iter().map(|&size| size as u64).collect();
+    let (ptrs, slab_refs) =
+        client.hydrate_ptrs(asset_ptrs, &resp.header.root_slab_handle, Some(&alloc_sizes))?;
 
     // Write group names and meta datas into the provided memory
     for ((ptr, asset_meta), group_name) in zip(&ptrs, asset_metas).zip(group_names) {
@@ -110,24 +307,80 @@ pub fn allocate_memory(
             std::ptr::copy_nonoverlapping(group_name.as_ptr(), name_dest, group_name.len());
         };
     }
-    Ok(AssetSyncContext::new(ptrs, asset_ptrs))
+    AssetSyncContext::new(client.clone(), ptrs, slab_refs, asset_ptrs, false)
 }
 
-pub fn send_mesh_command(meta_vec: Vec<AssetPtr>) -> Result<EngineResponse, String> {
+pub fn send_mesh_command(
+    client: &Arc<EngineClient>,
+    meta_vec: Vec<AssetPtr>,
+) -> Result<EngineResponse, SdkError> {
     let command = EngineCommand::send_mesh(&meta_vec);
-    CLIENT.send_command(command)
+    client.send_command(command)
+}
+
+/// Moves objects without reallocating or resending their geometry.
+/// `matrices` holds one 4x4 matrix per object UUID, laid out like the transforms slice
+pub fn send_transforms_command(
+    client: &Arc<EngineClient>,
+    uuids: Vec<Uuid>,
+    matrices: Vec<f32>,
+) -> Result<EngineResponse, SdkError> {
+    if matrices.len() != uuids.len() * 16 {
+        return Err(SdkError::Other(format!(
+            "Expected {} floats (4x4 per object) for {} objects, got {}",
+            uuids.len() * 16,
+            uuids.len(),
+            matrices.len()
+        )));
+    }
+    match client.engine_info() {
+        Some(info) if info.supports(CAP_TRANSFORM_UPDATES) => {}
+        Some(_) => {
+            return Err(SdkError::ProtocolMismatch(
+                "engine does not accept transform-only updates".to_string(),
+            ));
+        }
+        None => return Err(SdkError::NotStarted),
+    }
+
+    let command = transforms_command(&uuids, &matrices)?;
+    client.send_command(command)
+}
+
+#[cfg(feature = "protocol-v1")]
+fn transforms_command(uuids: &[Uuid], matrices: &[f32]) -> Result<EngineCommand, SdkError> {
+    Ok(EngineCommand::send_transforms(uuids, matrices))
+}
+
+/// The command only exists under protocol-v1; without it no engine reports the capability
+#[cfg(not(feature = "protocol-v1"))]
+fn transforms_command(_uuids: &[Uuid], _matrices: &[f32]) -> Result<EngineCommand, SdkError> {
+    Err(SdkError::ProtocolMismatch(
+        "engine does not accept transform-only updates".to_string(),
+    ))
 }
 
-pub fn standardize_groups_command(uuids: Vec<Uuid>) -> Result<EngineResponse, String> {
+pub fn standardize_groups_command(
+    client: &Arc<EngineClient>,
+    uuids: Vec<Uuid>,
+) -> Result<EngineResponse, SdkError> {
     let command = EngineCommand::standardize_groups(&uuids);
-    CLIENT.send_command(command)
+    client.send_command(command)
 }
 
 pub fn standardize_synced_groups_command(
+    client: &Arc<EngineClient>,
     uuids: Vec<Uuid>,
     surface_types: Vec<u32>,
-) -> Result<EngineResponse, String> {
+) -> Result<EngineResponse, SdkError> {
     let count = uuids.len();
+    if surface_types.len() != count {
+        return Err(SdkError::Other(format!(
+            "Expected {} surface types, got {}",
+            count,
+            surface_types.len()
+        )));
+    }
     let mut surface_vec: Vec<GroupSurface> = Vec::with_capacity(count);
 
     for i in 0..count {
@@ -136,12 +389,13 @@ pub fn standardize_synced_groups_command(
     }
 
     let command = EngineCommand::standardize_synced_groups(&surface_vec, 1);
-    CLIENT.send_command(command)
+    client.send_command(command)
 }
 
 pub fn set_surface_types_command(
+    client: &Arc<EngineClient>,
     group_surface_map: HashMap<Uuid, i64>,
-) -> Result<EngineResponse, String> {
+) -> Result<EngineResponse, SdkError> {
     let count = group_surface_map.len();
     let mut surface_vec: Vec<GroupSurface> = Vec::with_capacity(count);
 
@@ -151,103 +405,190 @@ pub fn set_surface_types_command(
     });
 
     let command = EngineCommand::set_surface_types(&surface_vec, 1);
-    CLIENT.send_command(command)
+    client.send_command(command)
 }
 
-pub fn drop_groups_command(uuids: Vec<Uuid>) -> Result<EngineResponse, String> {
+pub fn drop_groups_command(
+    client: &Arc<EngineClient>,
+    uuids: Vec<Uuid>,
+) -> Result<EngineResponse, SdkError> {
     let command = EngineCommand::drop_groups(&uuids, 1);
-    CLIENT.send_command(command)
+    client.send_command(command)
+}
+
+pub fn organize_objects_command(client: &Arc<EngineClient>) -> Result<EngineResponse, SdkError> {
+    let command = EngineCommand::organize_objects(1);
+    client.send_command(command)
 }
 
-pub fn organize_objects_command() -> Result<EngineResponse, String> {
+pub fn organize_objects_command_async(
+    client: &Arc<EngineClient>,
+) -> Result<PendingCommand, SdkError> {
     let command = EngineCommand::organize_objects(1);
-    CLIENT.send_command(command)
+    client.send_command_async(command)
 }
 
 pub fn extract_geometric_features_command(
+    client: &Arc<EngineClient>,
     uuids: Vec<Uuid>,
-) -> Result<EngineResponse, String> {
+) -> Result<EngineResponse, SdkError> {
     let command = EngineCommand::extract_geometric_features(&uuids, 1);
-    CLIENT.send_command(command)
+    client.send_command(command)
 }
 
-pub fn get_surface_types_command() -> Result<EngineResponse, String> {
+pub fn get_surface_types_command(
+    client: &Arc<EngineClient>,
+) -> Result<HashMap<Uuid, u64>, SdkError> {
     let command = EngineCommand::get_surface_types(1);
-    CLIENT.send_command(command)
+    let resp = client.send_command(command)?;
+
+    let surfaces = resp
+        .read_surface_types()
+        .map_err(|e| SdkError::Decode(e.to_string()))?;
+
+    Ok(surfaces
+        .iter()
+        .map(|surf| (surf.uuid, surf.surface_type))
+        .collect())
 }
 
 pub fn export_assets_command(
+    client: &Arc<EngineClient>,
     path: &str,
     target_bytes: u64,
     uuids: Vec<Uuid>,
-) -> Result<EngineResponse, String> {
+) -> Result<EngineResponse, SdkError> {
     let command = EngineCommand::export_assets(path, target_bytes, &uuids);
-    CLIENT.send_command(command)
+    client.send_command(command)
+}
+
+pub fn export_all_command(
+    client: &Arc<EngineClient>,
+    path: &str,
+    target_bytes: u64,
+) -> Result<EngineResponse, SdkError> {
+    let command = EngineCommand::export_all(path, target_bytes);
+    client.send_command(command)
 }
 
-pub fn export_all_command(path: &str, target_bytes: u64) -> Result<EngineResponse, String> {
+pub fn export_all_command_async(
+    client: &Arc<EngineClient>,
+    path: &str,
+    target_bytes: u64,
+) -> Result<PendingCommand, SdkError> {
     let command = EngineCommand::export_all(path, target_bytes);
-    CLIENT.send_command(command)
+    client.send_command_async(command)
 }
 
 pub fn export_mesh_tbo_command(
+    client: &Arc<EngineClient>,
     path: &str,
     target_bytes: u64,
     flags: u32,
     uuids: Vec<Uuid>,
-) -> Result<EngineResponse, String> {
+) -> Result<EngineResponse, SdkError> {
     let command = EngineCommand::export_mesh_tbo(path, target_bytes, flags, &uuids);
-    CLIENT.send_command(command)
+    client.send_command(command)
 }
 
 pub fn export_asset_tbo_command(
+    client: &Arc<EngineClient>,
     path: &str,
     target_bytes: u64,
     uuids: Vec<Uuid>,
-) -> Result<EngineResponse, String> {
+) -> Result<EngineResponse, SdkError> {
     let command = EngineCommand::export_asset_tbo(path, target_bytes, &uuids);
-    CLIENT.send_command(command)
+    client.send_command(command)
 }
 
-pub fn export_all_asset_tbo_command(path: &str, target_bytes: u64) -> Result<EngineResponse, String> {
+pub fn export_all_asset_tbo_command(
+    client: &Arc<EngineClient>,
+    path: &str,
+    target_bytes: u64,
+) -> Result<EngineResponse, SdkError> {
     let command = EngineCommand::export_all_asset_tbo(path, target_bytes);
-    CLIENT.send_command(command)
+    client.send_command(command)
 }
 
-pub fn drop_all_groups_command() -> Result<EngineResponse, String> {
+pub fn export_all_asset_tbo_command_async(
+    client: &Arc<EngineClient>,
+    path: &str,
+    target_bytes: u64,
+) -> Result<PendingCommand, SdkError> {
+    let command = EngineCommand::export_all_asset_tbo(path, target_bytes);
+    client.send_command_async(command)
+}
+
+pub fn drop_all_groups_command(client: &Arc<EngineClient>) -> Result<EngineResponse, SdkError> {
     let command = EngineCommand::drop_all_groups();
-    CLIENT.send_command(command)
+    client.send_command(command)
 }
 
 pub fn export_all_tbo_command(
+    client: &Arc<EngineClient>,
+    path: &str,
+    target_bytes: u64,
+    flags: u32,
+    target_point_count: u32,
+) -> Result<EngineResponse, SdkError> {
+    let command = EngineCommand::export_all_tbo(path, target_bytes, flags, target_point_count);
+    client.send_command(command)
+}
+
+pub fn export_all_tbo_command_async(
+    client: &Arc<EngineClient>,
     path: &str,
     target_bytes: u64,
     flags: u32,
     target_point_count: u32,
-) -> Result<EngineResponse, String> {
+) -> Result<PendingCommand, SdkError> {
     let command = EngineCommand::export_all_tbo(path, target_bytes, flags, target_point_count);
-    CLIENT.send_command(command)
+    client.send_command_async(command)
 }
 
-pub fn import_assets_command(paths: Vec<String>) -> Result<EngineResponse, String> {
+pub fn import_assets_command(
+    client: &Arc<EngineClient>,
+    paths: Vec<String>,
+) -> Result<EngineResponse, SdkError> {
     let path_refs: Vec<&str> = paths.iter().map(|s| s.as_str()).collect();
     let command = EngineCommand::import_assets(&path_refs);
-    CLIENT.send_command(command)
+    client.send_command(command)
 }
 
-  pub fn tbo_config_command(channel_mask: u32, target_point_count: u32) -> Result<EngineResponse, String> {
+pub fn import_assets_command_async(
+    client: &Arc<EngineClient>,
+    paths: Vec<String>,
+) -> Result<PendingCommand, SdkError> {
+    let path_refs: Vec<&str> = paths.iter().map(|s| s.as_str()).collect();
+    let command = EngineCommand::import_assets(&path_refs);
+    client.send_command_async(command)
+}
+
+pub fn tbo_config_command(
+    client: &Arc<EngineClient>,
+    channel_mask: u32,
+    target_point_count: u32,
+) -> Result<EngineResponse, SdkError> {
     let command = EngineCommand::tbo_config(channel_mask, target_point_count);
-    CLIENT.send_command(command)
+    client.send_command(command)
 }
 
-pub fn tbo_downsample_command(uuids: Vec<Uuid>) -> Result<EngineResponse, String> {
+pub fn tbo_downsample_command(
+    client: &Arc<EngineClient>,
+    uuids: Vec<Uuid>,
+) -> Result<EngineResponse, SdkError> {
     let command = EngineCommand::tbo_downsample(&uuids);
-    CLIENT.send_command(command)
+    client.send_command(command)
 }
 
-pub fn tbo_flush_command(path: &str, target_bytes: u64, batch_offset: u32) -> Result<EngineResponse, String> {
+pub fn tbo_flush_command(
+    client: &Arc<EngineClient>,
+    path: &str,
+    target_bytes: u64,
+    batch_offset: u32,
+) -> Result<EngineResponse, SdkError> {
     let command = EngineCommand::tbo_flush(path, target_bytes, batch_offset);
-    CLIENT.send_command(command)
+    client.send_command(command)
 }
 
 pub fn set_engine_dir(path: PathBuf) {
@@ -327,12 +668,19 @@ pub fn resolve_engine_binary_path() -> Option<PathBuf> {
     None
 }
 
-pub fn group_all_objects_command() -> Result<EngineResponse, String> {
+pub fn group_all_objects_command(client: &Arc<EngineClient>) -> Result<EngineResponse, SdkError> {
     let command = EngineCommand::group_all_objects();
-    CLIENT.send_command(command)
+    client.send_command(command)
+}
+
+pub fn embed_all_assets_command(client: &Arc<EngineClient>) -> Result<EngineResponse, SdkError> {
+    let command = EngineCommand::embed_all_assets(0);
+    client.send_command(command)
 }
 
-pub fn embed_all_assets_command() -> Result<EngineResponse, String> {
+pub fn embed_all_assets_command_async(
+    client: &Arc<EngineClient>,
+) -> Result<PendingCommand, SdkError> {
     let command = EngineCommand::embed_all_assets(0);
-    CLIENT.send_command(command)
+    client.send_command_async(command)
 }
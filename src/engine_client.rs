@@ -1,70 +1,285 @@
 use crossbeam::channel;
 use iceoryx2::prelude::*;
-use iceoryx2_bb_posix::file::AccessMode;
-use iceoryx2_bb_posix::shared_memory::{SharedMemory, SharedMemoryBuilder};
-use pivot_com_types::alloc::SlabRegistry;
 use pivot_com_types::asset_meta::AssetMeta;
 use pivot_com_types::asset_ptr::AssetPtr;
+use pivot_com_types::fields::Uuid;
 use pivot_com_types::{EngineCommand, EngineResponse, MeshPublish};
+use pyo3::Python;
 use std::process::Child;
 use std::ptr::NonNull;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
-
-use crate::command_thread::{CommandWork, spawn_command_thread};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use crate::asset_buffer::ViewGuard;
+use crate::asset_validation::{validate_allocation, validate_asset};
+use crate::command_options::{CommandOptions, deadline_after, scoped_options};
+use crate::command_thread::{CommandLimits, CommandWork, Outstanding, spawn_command_thread};
+use crate::engine_launch::EngineLaunchConfig;
+use crate::engine_logs::{EngineLogs, spawn_log_reader};
+use crate::error::SdkError;
+use crate::protocol::{CAP_ASSET_MAGIC, CAP_SLAB_GENERATIONS, EngineInfo};
+#[cfg(feature = "protocol-v1")]
+use crate::protocol::{handshake_command, verify_handshake};
+use crate::mesh_queue::{
+    MeshQueue, MeshQueueConfig, MeshQueueCounters, MeshQueueStats, idle_stats,
+};
 use crate::mesh_sync_thread::spawn_mesh_sync_thread;
+use crate::sdk_config::SdkConfig;
+use crate::service_names::ServiceNames;
+use crate::slab_table::{SlabRef, SlabTable};
+use crate::supervisor::{
+    EngineState, EngineStatus, RestartPolicy, STABLE_UPTIME, StateCallback, StopOutcome,
+    describe_exit, escalate_stop, spawn_supervisor_thread,
+};
+
+const DEFAULT_MAX_IN_FLIGHT: usize = 8;
+pub const DEFAULT_START_TIMEOUT: Duration = Duration::from_secs(30);
+/// Lower bound for the handshake so a slow service discovery doesn't leave it no time at all
+#[cfg(feature = "protocol-v1")]
+const MIN_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+pub const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+/// Lower bound for the stop command so a long drain doesn't make it fail instantly
+const MIN_STOP_COMMAND_TIMEOUT: Duration = Duration::from_millis(500);
+/// How long a dropped client waits for its engine to exit before SIGTERM
+const DROP_STOP_TIMEOUT: Duration = Duration::from_secs(2);
+/// How often wait_mesh_sync looks for a new session after the mesh thread went away
+const MESH_RECONNECT_INTERVAL: Duration = Duration::from_millis(50);
 
 #[derive(Debug)]
 struct ActiveState {
-    engine_process: Child,
+    /// False when attached to an engine we didn't spawn: we never stop, kill or restart it
+    owned: bool,
+    /// Only present for engines we spawned
+    engine_process: Option<Arc<Mutex<Child>>>,
     command_tx: channel::Sender<CommandWork>,
-    mesh_update_rx: channel::Receiver<MeshPublish>,
+    /// Mesh updates waiting to be polled, bounded by the client's MeshQueueConfig
+    mesh_updates: Arc<MeshQueue>,
     shutdown: Arc<AtomicBool>,
+    /// Set before we ask the engine to exit so the supervisor doesn't report a crash
+    expected_exit: Arc<AtomicBool>,
     threads: Vec<std::thread::JoinHandle<()>>,
-
-    slabs: Vec<SharedMemory>,
+    supervisor: Option<std::thread::JoinHandle<()>>,
+    /// Commands queued or awaiting a response
+    outstanding: Arc<AtomicUsize>,
+    /// Set by stop_engine(); new commands are refused while the session winds down
+    draining: bool,
+    /// Filled in by a successful handshake; slabs are never mapped without it
+    engine_info: Option<EngineInfo>,
+    /// When the engine became Ready; a crash after STABLE_UPTIME starts a fresh series of restarts
+    ready_since: Option<Instant>,
 }
 unsafe impl Send for ActiveState {}
 
 #[derive(Debug)]
 pub struct EngineClient {
     state: Mutex<Option<ActiveState>>,
-    node: Arc<Node<ipc::Service>>,
+    node: Mutex<Option<Arc<Node<ipc::Service>>>>,
+    /// Service prefix and iceoryx2 node settings; distinct prefixes let several engines run side by side
+    config: Mutex<SdkConfig>,
+    default_timeout: Mutex<Option<Duration>>,
+    max_in_flight: AtomicUsize,
+    status: Arc<EngineStatus>,
+    restart_policy: Mutex<RestartPolicy>,
+    restart_attempts: AtomicU32,
+    /// Set by stop() and cleared by start()/connect(), only ever under the `state` lock, so a
+    /// crash handled while the user stops the engine never restarts it
+    stop_requested: AtomicBool,
+    /// Binary and launch settings of the last start, reused for restarts
+    launch_spec: Mutex<Option<(String, EngineLaunchConfig)>>,
+    start_timeout: Mutex<Duration>,
+    /// Captured engine output; survives restarts so crash output stays readable
+    logs: Arc<EngineLogs>,
+    /// Engine memory mapped into this process; cleared whenever a session ends
+    slabs: Mutex<SlabTable>,
+    /// Guards of the contexts handing out views into `slabs`, revoked when it is cleared
+    view_guards: Mutex<Vec<Weak<ViewGuard>>>,
+    /// Capacity and policy of the mesh update queue; applies on the next start
+    mesh_queue_config: Mutex<MeshQueueConfig>,
+    mesh_queue_counters: Arc<MeshQueueCounters>,
 }
 
 impl EngineClient {
-    pub fn new() -> Self {
-        let node = NodeBuilder::new()
-            .create::<ipc::Service>()
-            .expect("Failed to create iceoryx2 node");
-
+    pub fn with_config(config: SdkConfig) -> Self {
         EngineClient {
             state: Mutex::new(None),
-            node: Arc::new(node),
+            node: Mutex::new(None),
+            config: Mutex::new(config),
+            default_timeout: Mutex::new(None),
+            max_in_flight: AtomicUsize::new(DEFAULT_MAX_IN_FLIGHT),
+            status: Arc::new(EngineStatus::default()),
+            restart_policy: Mutex::new(RestartPolicy::disabled()),
+            restart_attempts: AtomicU32::new(0),
+            stop_requested: AtomicBool::new(false),
+            launch_spec: Mutex::new(None),
+            start_timeout: Mutex::new(DEFAULT_START_TIMEOUT),
+            logs: Arc::new(EngineLogs::default()),
+            slabs: Mutex::new(SlabTable::default()),
+            view_guards: Mutex::new(Vec::new()),
+            mesh_queue_config: Mutex::new(MeshQueueConfig::default()),
+            mesh_queue_counters: Arc::new(MeshQueueCounters::default()),
+        }
+    }
+
+    pub fn config(&self) -> SdkConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// Replaces the SDK config; only allowed while no engine is connected
+    pub fn set_config(&self, config: SdkConfig) -> Result<(), SdkError> {
+        let guard = self.state.lock().unwrap();
+        if guard.is_some() {
+            return Err(SdkError::Other(
+                "Cannot change the SDK config while an engine is connected".to_string(),
+            ));
+        }
+        *self.config.lock().unwrap() = config;
+        // The next start builds a node with the new settings
+        *self.node.lock().unwrap() = None;
+        Ok(())
+    }
+
+    pub fn service_names(&self) -> ServiceNames {
+        self.config.lock().unwrap().service_names()
+    }
+
+    /// The last `n` lines the engine wrote to stdout/stderr
+    pub fn engine_logs(&self, n: usize) -> Vec<String> {
+        self.logs.tail(n)
+    }
+
+    pub fn engine_state(&self) -> EngineState {
+        self.status.get()
+    }
+
+    /// Called with every state transition, from whichever thread observed it
+    pub fn set_state_callback(&self, callback: Option<StateCallback>) {
+        self.status.set_callback(callback);
+    }
+
+    pub fn set_restart_policy(&self, policy: RestartPolicy) {
+        *self.restart_policy.lock().unwrap() = policy;
+    }
+
+    /// How many requests the command thread keeps outstanding at once; applies on the next start
+    pub fn set_max_in_flight(&self, max_in_flight: usize) {
+        self.max_in_flight.store(max_in_flight.max(1), Ordering::SeqCst);
+    }
+
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Bounds the mesh update queue; applies on the next start
+    pub fn set_mesh_queue(&self, config: MeshQueueConfig) {
+        *self.mesh_queue_config.lock().unwrap() = config;
+    }
+
+    /// Counters of the current session's mesh queue, or the configured queue if none is running
+    pub fn mesh_queue_stats(&self) -> MeshQueueStats {
+        match self.state.lock().unwrap().as_ref() {
+            Some(state) => state.mesh_updates.stats(),
+            None => idle_stats(
+                *self.mesh_queue_config.lock().unwrap(),
+                &self.mesh_queue_counters,
+            ),
+        }
+    }
+
+    /// Timeout applied to commands that don't carry their own; None waits forever
+    pub fn set_default_timeout(&self, timeout: Option<Duration>) {
+        *self.default_timeout.lock().unwrap() = timeout;
+    }
+
+    pub fn default_timeout(&self) -> Option<Duration> {
+        *self.default_timeout.lock().unwrap()
+    }
+
+    /// Lazily creates the iceoryx2 node so a broken IPC setup surfaces as an error on start
+    fn node(&self) -> Result<Arc<Node<ipc::Service>>, SdkError> {
+        let mut guard = self.node.lock().unwrap();
+        if let Some(node) = guard.as_ref() {
+            return Ok(node.clone());
         }
+
+        let node = Arc::new(self.config().create_node()?);
+        *guard = Some(node.clone());
+        Ok(node)
+    }
+
+    /// Sends a command using the options of the active command scope, if any
+    pub fn send_command(&self, cmd: EngineCommand) -> Result<EngineResponse, SdkError> {
+        self.send_command_with(cmd, scoped_options())
+    }
+
+    pub fn send_command_with(
+        &self,
+        cmd: EngineCommand,
+        options: CommandOptions,
+    ) -> Result<EngineResponse, SdkError> {
+        let pending = self.send_command_async_with(cmd, options)?;
+        loop {
+            if let Some(result) = pending.wait(None) {
+                return result;
+            }
+        }
+    }
+
+    /// Queues a command and returns immediately with a handle to its response
+    pub fn send_command_async(&self, cmd: EngineCommand) -> Result<PendingCommand, SdkError> {
+        self.send_command_async_with(cmd, scoped_options())
+    }
+
+    pub fn send_command_async_with(
+        &self,
+        cmd: EngineCommand,
+        options: CommandOptions,
+    ) -> Result<PendingCommand, SdkError> {
+        self.queue_command(cmd, options, false)
     }
 
-    pub fn send_command(&self, cmd: EngineCommand) -> Result<EngineResponse, String> {
+    /// Hands a command to the command thread; only stop() may still queue while draining
+    fn queue_command(
+        &self,
+        cmd: EngineCommand,
+        options: CommandOptions,
+        while_draining: bool,
+    ) -> Result<PendingCommand, SdkError> {
         let (tx, rx) = channel::bounded(1);
+        let limits = CommandLimits {
+            deadline: options
+                .timeout
+                .or_else(|| self.default_timeout())
+                .map(|timeout| (deadline_after(timeout), timeout)),
+            cancel: options.cancel.unwrap_or_default(),
+        };
 
         let guard = self.state.lock().unwrap();
-        let state = guard.as_ref().ok_or("Engine not started")?;
+        let state = guard.as_ref().ok_or(SdkError::NotStarted)?;
+        if state.draining && !while_draining {
+            return Err(SdkError::Transport(
+                "Engine is stopping and no longer accepts commands".to_string(),
+            ));
+        }
 
         state
             .command_tx
             .send(CommandWork {
                 cmd,
                 response_tx: tx,
+                limits: limits.clone(),
+                outstanding: Outstanding::new(&state.outstanding),
             })
-            .map_err(|e| format!("Failed to send command: {}", e))?;
-
-        drop(guard);
+            .map_err(|e| SdkError::Transport(format!("Failed to send command: {}", e)))?;
 
-        rx.recv()
-            .map_err(|e| format!("Failed to receive response: {}", e))?
+        Ok(PendingCommand {
+            rx,
+            limits,
+            result: Mutex::new(None),
+        })
     }
 
-    pub fn poll_mesh_sync(&self) -> Result<Option<MeshPublish>, String> {
+    pub fn poll_mesh_sync(&self) -> Result<Option<MeshPublish>, SdkError> {
         let guard = self.state.lock().unwrap();
 
         let state = match guard.as_ref() {
@@ -72,164 +287,701 @@ impl EngineClient {
             None => return Ok(None),
         };
 
-        match state.mesh_update_rx.try_recv() {
-            Ok(publish) => Ok(Some(publish)),
-            Err(_) => Ok(None),
+        Ok(state.mesh_updates.try_recv())
+    }
+
+    /// Takes up to `max` queued mesh updates (None = all of them) without blocking
+    pub fn drain_mesh_sync(&self, max: Option<usize>) -> Vec<MeshPublish> {
+        let guard = self.state.lock().unwrap();
+        let Some(state) = guard.as_ref() else {
+            return Vec::new();
+        };
+
+        let mut publishes = Vec::new();
+        while max.is_none_or(|max| publishes.len() < max) {
+            match state.mesh_updates.try_recv() {
+                Some(publish) => publishes.push(publish),
+                None => break,
+            }
         }
+        publishes
     }
 
-    pub fn start(&self, path: String) -> Result<(), String> {
-        let mut guard = self.state.lock().unwrap();
+    /// Blocks until a mesh update arrives or `timeout` elapses. Follows the session across
+    /// restarts; fails with NotStarted if no engine is connected
+    pub fn wait_mesh_sync(&self, timeout: Duration) -> Result<Option<MeshPublish>, SdkError> {
+        Ok(self.wait_mesh_update(timeout)?.map(|(publish, _)| publish))
+    }
 
-        if guard.is_some() {
-            return Ok(());
+    /// Like wait_mesh_sync, but also returns the queue the update came from so it can be
+    /// requeued if it can't be delivered
+    pub fn wait_mesh_update(
+        &self,
+        timeout: Duration,
+    ) -> Result<Option<(MeshPublish, Arc<MeshQueue>)>, SdkError> {
+        let deadline = deadline_after(timeout);
+        loop {
+            let mesh_updates = match self.state.lock().unwrap().as_ref() {
+                Some(state) => state.mesh_updates.clone(),
+                None => return Err(SdkError::NotStarted),
+            };
+
+            match mesh_updates.recv_deadline(deadline) {
+                Ok(publish) => return Ok(Some((publish, mesh_updates))),
+                Err(channel::RecvTimeoutError::Timeout) => return Ok(None),
+                // The mesh thread is gone; wait for a restart to install a new session
+                Err(channel::RecvTimeoutError::Disconnected) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Ok(None);
+                    }
+                    std::thread::sleep(remaining.min(MESH_RECONNECT_INTERVAL));
+                }
+            }
+        }
+    }
+
+    /// Spawns the engine and blocks until it is ready to accept commands or `timeout` elapses
+    pub fn start(
+        self: &Arc<Self>,
+        path: String,
+        config: EngineLaunchConfig,
+        timeout: Duration,
+    ) -> Result<(), SdkError> {
+        self.restart_attempts.store(0, Ordering::SeqCst);
+        *self.start_timeout.lock().unwrap() = timeout;
+        self.launch(path, config, false)
+    }
+
+    /// Spawns the engine; a `restart` is skipped if stop() was called since the crash
+    fn launch(
+        self: &Arc<Self>,
+        path: String,
+        config: EngineLaunchConfig,
+        restart: bool,
+    ) -> Result<(), SdkError> {
+        // State callbacks run Python code, so they only fire once the state lock is released
+        let spawned = {
+            let mut guard = self.state.lock().unwrap();
+
+            if guard.is_some() || (restart && self.stop_requested.load(Ordering::SeqCst)) {
+                return Ok(());
+            }
+            self.stop_requested.store(false, Ordering::SeqCst);
+
+            let node = self.node()?;
+            self.spawn_engine(&mut guard, node, path, config)
+        };
+
+        self.status.set(EngineState::Starting, None);
+        match spawned {
+            Ok((engine_process, ready_rx)) => {
+                self.finish_startup(Some(&*engine_process), &ready_rx)
+            }
+            Err(e) => {
+                self.status.set(EngineState::Stopped, None);
+                Err(e)
+            }
         }
+    }
 
-        let engine_process = std::process::Command::new(path)
+    /// Spawns the engine process and opens its session in `slot`
+    fn spawn_engine(
+        self: &Arc<Self>,
+        slot: &mut Option<ActiveState>,
+        node: Arc<Node<ipc::Service>>,
+        path: String,
+        config: EngineLaunchConfig,
+    ) -> Result<(Arc<Mutex<Child>>, channel::Receiver<Result<(), SdkError>>), SdkError> {
+        self.logs
+            .configure(
+                config.log_buffer_lines,
+                config.echo,
+                config.log_file_settings(),
+            )
+            .map_err(|e| {
+                SdkError::ProcessSpawn(format!("Failed to open engine log file: {}", e))
+            })?;
+        let mut command = config.command(&path);
+        self.config().apply_to(&mut command);
+        let mut child = command
             .spawn()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| SdkError::ProcessSpawn(format!("{}: {}", path, e)))?;
+        // Reader threads end on their own once the engine closes its pipes
+        if let Some(stdout) = child.stdout.take() {
+            spawn_log_reader(stdout, "engine", self.logs.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            spawn_log_reader(stderr, "engine:err", self.logs.clone());
+        }
+        let engine_process = Arc::new(Mutex::new(child));
+        *self.launch_spec.lock().unwrap() = Some((path, config));
+
+        let ready_rx = self.open_session(slot, node, Some(engine_process.clone()));
+        Ok((engine_process, ready_rx))
+    }
+
+    /// Attaches to an engine someone else launched. The SDK never stops or restarts it
+    pub fn connect(self: &Arc<Self>, timeout: Duration) -> Result<(), SdkError> {
+        *self.start_timeout.lock().unwrap() = timeout;
+        let ready_rx = {
+            let mut guard = self.state.lock().unwrap();
+
+            if guard.is_some() {
+                return Ok(());
+            }
+            self.stop_requested.store(false, Ordering::SeqCst);
 
+            let node = self.node()?;
+            self.open_session(&mut guard, node, None)
+        };
+
+        self.status.set(EngineState::Starting, None);
+        self.finish_startup(None, &ready_rx)
+    }
+
+    /// Spawns the worker threads (and the supervisor for engines we own) and installs the session
+    fn open_session(
+        self: &Arc<Self>,
+        slot: &mut Option<ActiveState>,
+        node: Arc<Node<ipc::Service>>,
+        engine_process: Option<Arc<Mutex<Child>>>,
+    ) -> channel::Receiver<Result<(), SdkError>> {
+        let timeout = *self.start_timeout.lock().unwrap();
         let (command_tx, command_rx) = channel::bounded::<CommandWork>(10);
-        let (mesh_update_tx, mesh_update_rx) = channel::unbounded::<MeshPublish>();
+        let client = Arc::downgrade(self);
+        let (mesh_update_tx, mesh_updates) = MeshQueue::new(
+            *self.mesh_queue_config.lock().unwrap(),
+            self.mesh_queue_counters.clone(),
+            // Only CoalesceByUuid calls this, on the mesh thread for every update it receives
+            Box::new(move |publish| client.upgrade()?.publish_uuids(publish).ok()),
+        );
+        let (ready_tx, ready_rx) = channel::bounded::<Result<(), SdkError>>(2);
+        let outstanding = Arc::new(AtomicUsize::new(0));
         let shutdown = Arc::new(AtomicBool::new(false));
-        let command_thread = spawn_command_thread(self.node.clone(), command_rx, shutdown.clone());
-        let mesh_sync_thread =
-            spawn_mesh_sync_thread(self.node.clone(), shutdown.clone(), mesh_update_tx);
+        let expected_exit = Arc::new(AtomicBool::new(false));
+        let names = Arc::new(self.service_names());
+        let command_thread = spawn_command_thread(
+            node.clone(),
+            names.clone(),
+            command_rx,
+            shutdown.clone(),
+            self.max_in_flight(),
+            timeout,
+            ready_tx.clone(),
+        );
+        let mesh_sync_thread = spawn_mesh_sync_thread(
+            node,
+            names,
+            shutdown.clone(),
+            mesh_update_tx,
+            timeout,
+            ready_tx,
+        );
+
+        let supervisor = engine_process.as_ref().map(|process| {
+            let client = Arc::downgrade(self);
+            spawn_supervisor_thread(
+                process.clone(),
+                shutdown.clone(),
+                expected_exit.clone(),
+                Box::new(move |reason| {
+                    if let Some(client) = client.upgrade() {
+                        client.handle_crash(reason);
+                    }
+                }),
+            )
+        });
 
-        *guard = Some(ActiveState {
+        *slot = Some(ActiveState {
+            owned: engine_process.is_some(),
             engine_process,
             command_tx,
             threads: vec![command_thread, mesh_sync_thread],
+            supervisor,
+            outstanding,
+            draining: false,
             shutdown: shutdown,
-            mesh_update_rx,
-            slabs: Vec::new(),
+            expected_exit,
+            mesh_updates,
+            engine_info: None,
+            ready_since: None,
         });
-        Ok(())
+        ready_rx
     }
 
-    pub fn stop(&self) -> Result<(), String> {
-        {
-            let guard = self.state.lock().unwrap();
-            if guard.is_none() {
-                return Ok(());
+    /// Waits for readiness with the state lock released, as the handshake goes through send_command
+    fn finish_startup(
+        &self,
+        engine_process: Option<&Mutex<Child>>,
+        ready_rx: &channel::Receiver<Result<(), SdkError>>,
+    ) -> Result<(), SdkError> {
+        let timeout = *self.start_timeout.lock().unwrap();
+        if let Err(e) = self.await_ready(engine_process, ready_rx, timeout) {
+            // Take the session out first so the lock isn't held while its threads are joined
+            let taken = self.state.lock().unwrap().take();
+            if let Some(state) = taken {
+                if let Some(process) = &state.engine_process {
+                    let _ = process.lock().unwrap().kill();
+                }
+                self.shut_down_session(state);
             }
+            self.status.set(EngineState::Stopped, None);
+            return Err(e);
+        }
+
+        if let Some(state) = self.state.lock().unwrap().as_mut() {
+            state.ready_since = Some(Instant::now());
         }
+        self.status.set(EngineState::Ready, None);
+        Ok(())
+    }
 
-        let command = EngineCommand::stop_engine();
-        let res = self.send_command(command);
+    /// Waits for both worker threads to open their services, then negotiates the protocol
+    fn await_ready(
+        &self,
+        engine_process: Option<&Mutex<Child>>,
+        ready_rx: &channel::Receiver<Result<(), SdkError>>,
+        timeout: Duration,
+    ) -> Result<(), SdkError> {
+        let deadline = deadline_after(timeout);
+        let mut waiting_for = 2;
+
+        while waiting_for > 0 {
+            if let Some(process) = engine_process {
+                let exit = process
+                    .lock()
+                    .unwrap()
+                    .try_wait()
+                    .map_err(|e| SdkError::ProcessSpawn(e.to_string()))?;
+                if let Some(status) = exit {
+                    return Err(SdkError::EngineExited(describe_exit(status)));
+                }
+            }
 
-        let mut guard = self.state.lock().unwrap();
-        if let Some(mut state) = guard.take() {
-            if let Err(e) = res {
-                eprintln!(
-                    "Failed to send stop command to engine, killing process: {}",
-                    e
-                );
-                let _ = state.engine_process.kill();
+            match ready_rx.recv_timeout(Duration::from_millis(50)) {
+                Ok(Ok(())) => waiting_for -= 1,
+                Ok(Err(e)) => return Err(e),
+                Err(channel::RecvTimeoutError::Timeout) => {
+                    if Instant::now() >= deadline {
+                        return Err(SdkError::ServiceDiscoveryTimeout(format!(
+                            "{}/*",
+                            self.config.lock().unwrap().service_prefix
+                        )));
+                    }
+                }
+                Err(channel::RecvTimeoutError::Disconnected) => {
+                    return Err(SdkError::Transport(
+                        "Worker thread exited during startup".to_string(),
+                    ));
+                }
             }
+        }
+
+        let info = self.handshake(deadline)?;
+        log::info!(
+            "Connected to engine {} (protocol {})",
+            info.version, info.protocol_version
+        );
 
-            state.shutdown.store(true, Ordering::SeqCst);
+        if let Some(state) = self.state.lock().unwrap().as_mut() {
+            state.engine_info = Some(info);
+        }
+        Ok(())
+    }
 
-            for handle in state.threads {
-                let _ = handle.join();
+    /// Negotiates the protocol version and capabilities with a freshly started engine
+    #[cfg(feature = "protocol-v1")]
+    fn handshake(&self, deadline: Instant) -> Result<EngineInfo, SdkError> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let resp = self.send_command_with(
+            handshake_command(),
+            CommandOptions {
+                timeout: Some(remaining.max(MIN_HANDSHAKE_TIMEOUT)),
+                cancel: None,
+            },
+        )?;
+        verify_handshake(&resp)
+    }
+
+    /// Without protocol v1 there is nothing to negotiate; the engine gets the baseline command set
+    #[cfg(not(feature = "protocol-v1"))]
+    fn handshake(&self, _deadline: Instant) -> Result<EngineInfo, SdkError> {
+        Ok(EngineInfo::legacy())
+    }
+
+    /// Version and capabilities reported by the running engine
+    pub fn engine_info(&self) -> Option<EngineInfo> {
+        self.state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|state| state.engine_info.clone())
+    }
+
+    /// Stops the worker threads of a session that has already been taken out of `state`
+    /// and unmaps the engine's memory
+    fn shut_down_session(&self, state: ActiveState) {
+        // Runs on the supervisor without the GIL; the views are released on next use instead
+        self.unmap_slabs();
+        state.shutdown.store(true, Ordering::SeqCst);
+        for handle in state.threads {
+            let _ = handle.join();
+        }
+        if let Some(process) = &state.engine_process {
+            let _ = process.lock().unwrap().wait();
+        }
+    }
+
+    /// Runs on the supervisor thread after the engine died unexpectedly.
+    /// Tears down the stale session (including every mapped slab) and restarts if the policy allows
+    fn handle_crash(self: &Arc<Self>, reason: String) {
+        // A failed start is reported by launch() itself
+        if self.status.get() != EngineState::Ready {
+            return;
+        }
+        // stop_engine() is winding the session down or already took it, so this exit was
+        // requested after all
+        let taken = {
+            let mut guard = self.state.lock().unwrap();
+            if self.stop_requested.load(Ordering::SeqCst) {
+                None
+            } else {
+                guard.take()
             }
+        };
+        let Some(state) = taken else {
+            return;
+        };
+        log::error!("Engine crashed: {}", reason);
+        // Only consecutive crashes count against max_restarts
+        if state
+            .ready_since
+            .is_some_and(|since| since.elapsed() >= STABLE_UPTIME)
+        {
+            self.restart_attempts.store(0, Ordering::SeqCst);
+        }
 
-            let _ = state.engine_process.wait();
+        // The supervisor handle is our own thread, it finishes once we return
+        self.shut_down_session(state);
+        self.status.set(EngineState::Crashed, Some(&reason));
+        // stop() found no session and reported Stopped before we got here; it has the last word
+        if self.stop_requested() {
+            self.status.set(EngineState::Stopped, None);
+            return;
+        }
 
-            println!("All threads joined. SDK is clean.");
+        let policy = self.restart_policy.lock().unwrap().clone();
+        let attempt = self.restart_attempts.fetch_add(1, Ordering::SeqCst);
+        if attempt >= policy.max_restarts {
+            return;
         }
 
-        Ok(())
+        std::thread::sleep(policy.backoff(attempt));
+
+        // stop_engine() or start_engine() during the backoff takes precedence over our restart
+        if self.status.get() != EngineState::Crashed || self.stop_requested() {
+            return;
+        }
+
+        let (path, config) = match self.launch_spec.lock().unwrap().clone() {
+            Some(spec) => spec,
+            None => return,
+        };
+        log::info!(
+            "Restarting engine (attempt {} of {})",
+            attempt + 1,
+            policy.max_restarts
+        );
+        if let Err(e) = self.launch(path, config, true) {
+            log::error!("Engine restart failed: {}", e);
+            self.status.set(EngineState::Crashed, Some(&e.to_string()));
+        }
+    }
+
+    /// Stops an engine we own, or just detaches from one we attached to.
+    ///
+    /// With `drain`, up to half of `timeout` is spent letting queued commands finish and
+    /// waiting for queued mesh updates to be polled. The engine then gets the rest of
+    /// `timeout` to exit after the stop command before it is sent SIGTERM and finally killed
+    pub fn stop(&self, timeout: Duration, drain: bool) -> Result<StopOutcome, SdkError> {
+        let deadline = deadline_after(timeout);
+        let session = {
+            let mut guard = self.state.lock().unwrap();
+            self.stop_requested.store(true, Ordering::SeqCst);
+            guard.as_mut().map(|state| {
+                state.draining = true;
+                state.expected_exit.store(true, Ordering::SeqCst);
+                (
+                    state.owned,
+                    state.outstanding.clone(),
+                    state.mesh_updates.clone(),
+                )
+            })
+        };
+        let Some((owned, outstanding, mesh_updates)) = session else {
+            self.status.set(EngineState::Stopped, None);
+            return Ok(StopOutcome::NotRunning);
+        };
+
+        if drain {
+            drain_session(&outstanding, &mesh_updates, deadline_after(timeout / 2));
+        }
+
+        let asked = owned && {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let options = CommandOptions {
+                timeout: Some(remaining.max(MIN_STOP_COMMAND_TIMEOUT)),
+                cancel: None,
+            };
+            let sent = self
+                .queue_command(EngineCommand::stop_engine(), options, true)
+                .and_then(|pending| loop {
+                    if let Some(result) = pending.wait(None) {
+                        break result;
+                    }
+                });
+            if let Err(e) = &sent {
+                log::warn!("Failed to send stop command to engine: {}", e);
+            }
+            sent.is_ok()
+        };
+
+        // Release the lock before joining so a supervisor racing us in handle_crash can't deadlock
+        let taken = self.state.lock().unwrap().take();
+        let outcome = match taken {
+            Some(state) => {
+                let guards = self.unmap_slabs();
+                Python::attach(|py| {
+                    for guard in guards {
+                        guard.release_views_or_warn(py);
+                    }
+                });
+                state.shutdown.store(true, Ordering::SeqCst);
+
+                for handle in state.threads {
+                    let _ = handle.join();
+                }
+                if let Some(supervisor) = state.supervisor {
+                    let _ = supervisor.join();
+                }
+
+                match &state.engine_process {
+                    Some(process) => escalate_stop(process, asked, deadline),
+                    None => StopOutcome::Detached,
+                }
+            }
+            // The engine crashed while we were draining and handle_crash cleaned up
+            None => StopOutcome::NotRunning,
+        };
+        log::info!("Engine stopped ({:?})", outcome);
+        self.status.set(EngineState::Stopped, None);
+
+        Ok(outcome)
+    }
+
+    /// Whether stop() was called since the last start()/connect()
+    fn stop_requested(&self) -> bool {
+        let _guard = self.state.lock().unwrap();
+        self.stop_requested.load(Ordering::SeqCst)
+    }
+
+    /// Whether the current engine was spawned by this client (None if not connected)
+    pub fn owns_engine(&self) -> Option<bool> {
+        self.state.lock().unwrap().as_ref().map(|state| state.owned)
     }
 
-    ///Takes asset ptrs and hydrates them into local pointers into shared memory for AssetMetas
-    /// If we dont have any slabs registered yet we use the root_handle as the slab registry is the first address there
+    /// Maps the slabs registered under `root_handle` and validates `asset_ptrs` into AssetMeta
+    /// pointers; fresh allocations pass `allocation_sizes` as their headers aren't written yet
     pub fn hydrate_ptrs(
         &self,
         asset_ptrs: &[AssetPtr],
         root_handle: &[u8],
-    ) -> Result<Vec<NonNull<AssetMeta>>, String> {
-        let mut guard = self.state.lock().unwrap();
-        let state = guard.as_mut().expect("Engine not started");
-
-        //If this is engine startup we open the root shm which contains the slab registry for later syncing
-        if state.slabs.is_empty() {
-            state.slabs.push(open_shm(root_handle)?);
-        }
+        allocation_sizes: Option<&[u64]>,
+    ) -> Result<(Vec<NonNull<AssetMeta>>, Vec<SlabRef>), SdkError> {
+        let (use_generations, check_magic) = {
+            let guard = self.state.lock().unwrap();
+            let state = guard.as_ref().ok_or(SdkError::NotStarted)?;
+            match &state.engine_info {
+                Some(info) => (
+                    info.supports(CAP_SLAB_GENERATIONS),
+                    info.supports(CAP_ASSET_MAGIC),
+                ),
+                None => {
+                    return Err(SdkError::ProtocolMismatch(
+                        "refusing to map engine memory before a successful handshake".to_string(),
+                    ));
+                }
+            }
+        };
 
-        Self::ensure_slabs_synced(state); // Ensure that we have the correct number of slabs
+        let mut slabs = self.slabs.lock().unwrap();
+        slabs.sync(root_handle, use_generations)?;
 
         let mut ptrs = Vec::with_capacity(asset_ptrs.len());
+        let mut slab_refs = Vec::with_capacity(asset_ptrs.len());
 
-        for asset_ptr in asset_ptrs {
+        for (i, asset_ptr) in asset_ptrs.iter().enumerate() {
             let (slab_index, offset) = asset_ptr.unpack();
+            let (bounds, slab_ref) = slabs.lookup(slab_index as usize, offset as u64)?;
+
+            let ptr = match allocation_sizes {
+                Some(sizes) => {
+                    let size = sizes.get(i).copied().ok_or_else(|| {
+                        SdkError::Decode(format!(
+                            "engine returned {} AssetPtrs for {} allocations",
+                            asset_ptrs.len(),
+                            sizes.len()
+                        ))
+                    })?;
+                    let meta_ptr = validate_allocation(&bounds, offset as u64, size)?;
+                    unsafe { NonNull::new_unchecked(meta_ptr) }
+                }
+                None => validate_asset(&bounds, offset as u64, check_magic)?,
+            };
+            ptrs.push(ptr);
+            slab_refs.push(slab_ref);
+        }
 
-            let shm = &state
-                .slabs
-                .get(slab_index as usize)
-                .ok_or_else(|| format!("Slab index {} is out of bounds", slab_index))?;
+        Ok((ptrs, slab_refs))
+    }
 
-            unsafe {
-                let raw_ptr = shm.base_address().as_ptr().add(offset as usize) as *mut AssetMeta;
-                ptrs.push(NonNull::new_unchecked(raw_ptr));
-            }
-        };
+    /// UUIDs of the assets a mesh update refers to, read from their headers
+    fn publish_uuids(&self, publish: &MeshPublish) -> Result<Vec<Uuid>, SdkError> {
+        let asset_ptrs = publish
+            .read_send_mesh()
+            .map_err(|e| SdkError::Decode(e.to_string()))?;
+        let (ptrs, _) = self.hydrate_ptrs(asset_ptrs, &publish.header.root_slab_handle, None)?;
+        Ok(ptrs.iter().map(|ptr| unsafe { ptr.as_ref().uuid }).collect())
+    }
 
-        Ok(ptrs)
-        
+    /// False once the slab a context's pointers came from has been unmapped or replaced
+    pub fn slab_is_current(&self, slab: &SlabRef) -> bool {
+        self.slabs.lock().unwrap().is_current(slab)
     }
 
-    ///Checks the returned number of slabs and opens the ones at the end of the list until we have the correct ones open as the engine will only ever create new ones at the end
-    fn ensure_slabs_synced(state: &mut ActiveState) {
-        let registry = unsafe { &*(state.slabs[0].base_address().as_ptr() as *const SlabRegistry) };
-        let target_count = registry.num_slabs as usize;
+    /// Revokes `guard` when the engine's memory is unmapped
+    pub fn track_views(&self, guard: &Arc<ViewGuard>) {
+        let mut guards = self.view_guards.lock().unwrap();
+        guards.retain(|guard| guard.strong_count() > 0);
+        guards.push(Arc::downgrade(guard));
+    }
 
-        // If the Engine added a slab, Blender catches up here.
-        while state.slabs.len() < target_count {
-            let next_idx = state.slabs.len();
-            let handle = &registry.slab_handles[next_idx];
+    /// Unmaps every slab and revokes the guards of the contexts that pointed into them
+    fn unmap_slabs(&self) -> Vec<Arc<ViewGuard>> {
+        self.slabs.lock().unwrap().clear();
+        let guards: Vec<_> = std::mem::take(&mut *self.view_guards.lock().unwrap())
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect();
+        for guard in &guards {
+            guard.revoke("the engine was stopped");
+        }
+        guards
+    }
+}
 
-            match open_shm(handle) {
-                Ok(shm) => {
-                    println!(
-                        "[SDK] Auto-mapped new memory slab [{}]: {:?}",
-                        next_idx,
-                        bytes_to_clean_str(handle)
-                    );
-                    state.slabs.push(shm);
-                }
-                Err(e) => {
-                    eprintln!("[SDK] Failed to map discovered slab: {}", e);
-                    break;
-                }
-            }
+impl Drop for EngineClient {
+    /// Keeps an engine we spawned from outliving the client that owns it
+    fn drop(&mut self) {
+        if self.state.get_mut().unwrap().is_some() {
+            let _ = self.stop(DROP_STOP_TIMEOUT, false);
         }
     }
 }
 
-pub fn bytes_to_clean_str(bytes: &[u8]) -> &[u8] {
-    // Look for the first null terminator, or use the whole slice if none found
-    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
-    &bytes[..len]
+/// Waits until every queued command has been answered and every mesh update polled, or `deadline`
+fn drain_session(
+    outstanding: &AtomicUsize,
+    mesh_updates: &MeshQueue,
+    deadline: Instant,
+) {
+    loop {
+        let commands = outstanding.load(Ordering::SeqCst);
+        let updates = mesh_updates.len();
+        if commands == 0 && updates == 0 {
+            return;
+        }
+        if Instant::now() >= deadline {
+            log::warn!(
+                "Stopping with {} commands and {} mesh updates still pending",
+                commands, updates
+            );
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
 }
 
-///Opens existing shm by u8 handle
-fn open_shm(handle: &[u8]) -> Result<SharedMemory, String> {
-    let clean_handle = bytes_to_clean_str(handle);
-    let file_name = match FileName::new(clean_handle) {
-        Ok(f) => f,
-        Err(e) => {
-            return Err(format!(
-                "invalid shared memory name '{:?}': {:?}",
-                clean_handle, e
-            ));
+/// A queued command whose response may not have arrived yet.
+///
+/// Safe to share between threads: the response is only ever taken from the channel under
+/// the `result` lock, so concurrent waiters all observe the same outcome
+pub struct PendingCommand {
+    rx: channel::Receiver<Result<EngineResponse, SdkError>>,
+    limits: CommandLimits,
+    result: Mutex<Option<Result<EngineResponse, SdkError>>>,
+}
+
+impl PendingCommand {
+    /// Checks for a response without blocking, returning true once the command has finished
+    pub fn poll(&self) -> bool {
+        let mut result = self.result.lock().unwrap();
+        if result.is_some() {
+            return true;
         }
-    };
 
-    let shm = {
-        SharedMemoryBuilder::new(&file_name)
-            .open_existing(AccessMode::ReadWrite)
-            .expect("Failed to open shm")
-    };
-    Ok(shm)
+        match self.rx.try_recv() {
+            Ok(resp) => *result = Some(resp.and_then(check_response)),
+            Err(channel::TryRecvError::Empty) => {
+                if let Some(e) = self.limits.abandoned() {
+                    *result = Some(Err(e));
+                }
+            }
+            Err(channel::TryRecvError::Disconnected) => {
+                *result = Some(Err(SdkError::Transport(
+                    "Command thread dropped the request".to_string(),
+                )));
+            }
+        }
+
+        result.is_some()
+    }
+
+    /// Blocks until the command finishes or `timeout` elapses, returning None in the latter case.
+    /// Wakes up periodically so the command's own timeout or cancel is honoured while the command thread is busy
+    pub fn wait(&self, timeout: Option<Duration>) -> Option<Result<EngineResponse, SdkError>> {
+        let give_up = timeout.map(deadline_after);
+
+        while !self.poll() {
+            let mut slice = Duration::from_millis(50);
+            if let Some(give_up) = give_up {
+                let now = Instant::now();
+                if now >= give_up {
+                    return None;
+                }
+                slice = slice.min(give_up - now);
+            }
+
+            // Only wait for the response to become available; poll() takes it under the lock
+            let mut select = channel::Select::new();
+            select.recv(&self.rx);
+            let _ = select.ready_timeout(slice);
+        }
+
+        self.result.lock().unwrap().clone()
+    }
+
+    /// Aborts the command; the command thread drops it as soon as it notices
+    pub fn cancel(&self) {
+        self.limits.cancel.cancel();
+    }
+}
+
+/// Turns an error response from the engine into a typed rejection
+fn check_response(resp: EngineResponse) -> Result<EngineResponse, SdkError> {
+    match resp.read_error() {
+        Some(msg) => Err(SdkError::Rejected(msg.to_string())),
+        None => Ok(resp),
+    }
 }
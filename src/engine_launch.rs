@@ -0,0 +1,111 @@
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::engine_logs::DEFAULT_LOG_LINES;
+
+/// How the pivot_engine process is launched and how its output is captured.
+///
+/// Args:
+///     args: Extra command line arguments for the engine
+///     env: Environment variables added to the inherited environment
+///     cwd: Working directory (None = inherit)
+///     log_file: Optional file the engine output is tee'd to
+///     log_file_max_bytes: Rotate the log file once it exceeds this size (0 = never)
+///     log_file_backups: Number of rotated files to keep
+///     log_buffer_lines: Lines kept in memory for engine_logs()
+///     echo: Also print engine output to this process' stdout (default False)
+#[pyclass(get_all, set_all)]
+#[derive(Debug, Clone)]
+pub struct EngineLaunchConfig {
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    pub cwd: Option<String>,
+    pub log_file: Option<String>,
+    pub log_file_max_bytes: u64,
+    pub log_file_backups: u32,
+    pub log_buffer_lines: usize,
+    pub echo: bool,
+}
+
+impl Default for EngineLaunchConfig {
+    fn default() -> Self {
+        EngineLaunchConfig {
+            args: Vec::new(),
+            env: HashMap::new(),
+            cwd: None,
+            log_file: None,
+            log_file_max_bytes: 10 * 1024 * 1024,
+            log_file_backups: 3,
+            log_buffer_lines: DEFAULT_LOG_LINES,
+            echo: false,
+        }
+    }
+}
+
+#[pymethods]
+impl EngineLaunchConfig {
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (
+        args=Vec::new(),
+        env=HashMap::new(),
+        cwd=None,
+        log_file=None,
+        log_file_max_bytes=10 * 1024 * 1024,
+        log_file_backups=3,
+        log_buffer_lines=DEFAULT_LOG_LINES,
+        echo=false,
+    ))]
+    fn new(
+        args: Vec<String>,
+        env: HashMap<String, String>,
+        cwd: Option<String>,
+        log_file: Option<String>,
+        log_file_max_bytes: u64,
+        log_file_backups: u32,
+        log_buffer_lines: usize,
+        echo: bool,
+    ) -> Self {
+        EngineLaunchConfig {
+            args,
+            env,
+            cwd,
+            log_file,
+            log_file_max_bytes,
+            log_file_backups,
+            log_buffer_lines,
+            echo,
+        }
+    }
+}
+
+impl EngineLaunchConfig {
+    /// Builds the engine command with piped stdout/stderr for log capture
+    pub fn command(&self, path: &str) -> Command {
+        let mut command = Command::new(path);
+
+        command.args(&self.args);
+        command.envs(&self.env);
+        if let Some(cwd) = &self.cwd {
+            command.current_dir(cwd);
+        }
+
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        command
+    }
+
+    pub fn log_file_settings(&self) -> Option<(PathBuf, u64, u32)> {
+        self.log_file.as_ref().map(|path| {
+            (
+                PathBuf::from(path),
+                self.log_file_max_bytes,
+                self.log_file_backups,
+            )
+        })
+    }
+}
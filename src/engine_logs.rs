@@ -0,0 +1,239 @@
+//! Capture of the engine's stdout/stderr.
+//!
+//! Lines land in a bounded in-memory ring buffer (read from Python via `engine_logs`)
+//! and are optionally tee'd to a size-rotated log file.
+
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub const DEFAULT_LOG_LINES: usize = 2000;
+/// Lines engine_logs() returns when no count is given
+pub const DEFAULT_TAIL_LINES: usize = 100;
+
+/// Log file that is renamed to `<path>.1`, `<path>.2`, ... once it exceeds `max_bytes`
+#[derive(Debug)]
+struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    backups: u32,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_bytes: u64, backups: u32) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            max_bytes,
+            backups,
+            file,
+            written,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.max_bytes > 0 && self.written + line.len() as u64 + 1 > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.written += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let backup = |i: u32| PathBuf::from(format!("{}.{}", self.path.display(), i));
+
+        if self.backups == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            for i in (1..self.backups).rev() {
+                let from = backup(i);
+                if from.exists() {
+                    fs::rename(&from, backup(i + 1))?;
+                }
+            }
+            fs::rename(&self.path, backup(1))?;
+            self.file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+}
+
+/// Most recent engine output lines, shared by the reader threads and Python
+#[derive(Debug)]
+pub struct EngineLogs {
+    lines: Mutex<VecDeque<String>>,
+    capacity: Mutex<usize>,
+    file: Mutex<Option<RotatingFile>>,
+    echo: Mutex<bool>,
+}
+
+impl Default for EngineLogs {
+    fn default() -> Self {
+        EngineLogs {
+            lines: Mutex::new(VecDeque::new()),
+            capacity: Mutex::new(DEFAULT_LOG_LINES),
+            file: Mutex::new(None),
+            echo: Mutex::new(false),
+        }
+    }
+}
+
+impl EngineLogs {
+    /// Applies the capture settings of a new launch; buffered lines from earlier runs are kept
+    pub fn configure(
+        &self,
+        capacity: usize,
+        echo: bool,
+        log_file: Option<(PathBuf, u64, u32)>,
+    ) -> std::io::Result<()> {
+        *self.capacity.lock().unwrap() = capacity.max(1);
+        *self.echo.lock().unwrap() = echo;
+        *self.file.lock().unwrap() = match log_file {
+            Some((path, max_bytes, backups)) => Some(RotatingFile::open(path, max_bytes, backups)?),
+            None => None,
+        };
+        Ok(())
+    }
+
+    pub fn push(&self, source: &str, line: String) {
+        // echo=True asks for the engine's own output on our stdout, so it bypasses log
+        if *self.echo.lock().unwrap() {
+            println!("[{}] {}", source, line);
+        }
+
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            if let Err(e) = file.write_line(&line) {
+                log::warn!("Failed to write engine log file: {}", e);
+            }
+        }
+
+        let capacity = *self.capacity.lock().unwrap();
+        let mut lines = self.lines.lock().unwrap();
+        while lines.len() >= capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// The last `n` lines, oldest first
+    pub fn tail(&self, n: usize) -> Vec<String> {
+        let lines = self.lines.lock().unwrap();
+        lines.iter().skip(lines.len().saturating_sub(n)).cloned().collect()
+    }
+}
+
+/// Forwards every line of `stream` into `logs` until the engine closes it
+pub fn spawn_log_reader<R: Read + Send + 'static>(
+    stream: R,
+    source: &'static str,
+    logs: Arc<EngineLogs>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    // Engine output isn't guaranteed to be UTF-8, keep what we can
+                    let line = String::from_utf8_lossy(&buf);
+                    logs.push(source, line.trim_end_matches(['\n', '\r']).to_string());
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Fresh directory for one test's log files
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("elbo_sdk_logs_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    fn backup(path: &Path, i: u32) -> PathBuf {
+        PathBuf::from(format!("{}.{}", path.display(), i))
+    }
+
+    #[test]
+    fn rotation_keeps_the_newest_backups() {
+        let dir = temp_dir("rotate");
+        let path = dir.join("engine.log");
+        // Two 4 byte lines fit, the third rotates
+        let mut file = RotatingFile::open(path.clone(), 10, 2).unwrap();
+        for line in ["aaa", "bbb", "ccc", "ddd", "eee", "fff", "ggg"] {
+            file.write_line(line).unwrap();
+        }
+
+        assert_eq!(read(&path), "ggg\n");
+        assert_eq!(read(&backup(&path, 1)), "eee\nfff\n");
+        assert_eq!(read(&backup(&path, 2)), "ccc\nddd\n");
+        assert!(!backup(&path, 3).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn no_backups_truncates_in_place() {
+        let dir = temp_dir("truncate");
+        let path = dir.join("engine.log");
+        let mut file = RotatingFile::open(path.clone(), 10, 0).unwrap();
+        for line in ["aaa", "bbb", "ccc"] {
+            file.write_line(line).unwrap();
+        }
+
+        assert_eq!(read(&path), "ccc\n");
+        assert!(!backup(&path, 1).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reopening_counts_what_is_already_there() {
+        let dir = temp_dir("reopen");
+        let path = dir.join("engine.log");
+        fs::write(&path, "aaa\nbbb\n").unwrap();
+
+        let mut file = RotatingFile::open(path.clone(), 10, 1).unwrap();
+        file.write_line("ccc").unwrap();
+
+        assert_eq!(read(&path), "ccc\n");
+        assert_eq!(read(&backup(&path, 1)), "aaa\nbbb\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn zero_max_bytes_never_rotates() {
+        let dir = temp_dir("unbounded");
+        let path = dir.join("engine.log");
+        let mut file = RotatingFile::open(path.clone(), 0, 1).unwrap();
+        for line in ["aaa", "bbb", "ccc"] {
+            file.write_line(line).unwrap();
+        }
+
+        assert_eq!(read(&path), "aaa\nbbb\nccc\n");
+        assert!(!backup(&path, 1).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
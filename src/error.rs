@@ -0,0 +1,248 @@
+use pyo3::create_exception;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use std::fmt;
+use std::time::Duration;
+
+create_exception!(
+    elbo_sdk_rust,
+    EngineError,
+    PyRuntimeError,
+    "Base class for every error raised by the engine bindings."
+);
+create_exception!(
+    elbo_sdk_rust,
+    EngineNotStartedError,
+    EngineError,
+    "Raised when a command is issued before start_engine()."
+);
+create_exception!(
+    elbo_sdk_rust,
+    EngineTransportError,
+    EngineError,
+    "Raised when a command could not be delivered to or answered by the engine."
+);
+create_exception!(
+    elbo_sdk_rust,
+    EngineCommandRejected,
+    EngineError,
+    "Raised when the engine received a command but refused to execute it."
+);
+create_exception!(
+    elbo_sdk_rust,
+    EngineTimeoutError,
+    EngineError,
+    "Raised when the engine did not answer a command within its timeout."
+);
+create_exception!(
+    elbo_sdk_rust,
+    EngineCommandCancelled,
+    EngineError,
+    "Raised when a pending command was aborted through its CancellationToken."
+);
+create_exception!(
+    elbo_sdk_rust,
+    EngineProtocolError,
+    EngineError,
+    "Raised when the running engine speaks a different protocol or struct layout than this SDK."
+);
+create_exception!(
+    elbo_sdk_rust,
+    EngineMemoryError,
+    EngineError,
+    "Raised when engine memory is out of bounds, corrupt, or was unmapped or replaced since a context was created."
+);
+
+#[derive(Debug, Clone)]
+pub enum SdkError {
+    /// No engine is running for this client
+    NotStarted,
+    /// The engine binary could not be located or launched
+    ProcessSpawn(String),
+    /// The engine process exited before it became ready
+    EngineExited(String),
+    /// The iceoryx2 node or one of its ports could not be created
+    Ipc(String),
+    /// The engine never registered the named service
+    ServiceDiscoveryTimeout(String),
+    /// No shared memory sample could be loaned for a request
+    ShmLoan(String),
+    /// A loaned request could not be published to the engine
+    ShmSend(String),
+    /// The command could not be delivered to, or answered by, the engine
+    Transport(String),
+    /// The engine answered with an error response
+    Rejected(String),
+    /// No response arrived before the command's deadline
+    Timeout(Duration),
+    /// The caller cancelled the command before a response arrived
+    Cancelled,
+    /// A shared memory slab could not be opened or mapped
+    SlabMapping { handle: String, reason: String },
+    /// An AssetPtr points outside of the slabs currently mapped
+    AssetPtrOutOfBounds {
+        slab_index: u64,
+        offset: u64,
+        reason: String,
+    },
+    /// An AssetMeta failed its alignment or integrity checks
+    CorruptAsset {
+        slab_index: u64,
+        offset: u64,
+        reason: String,
+    },
+    /// The slab a context points into was unmapped or replaced after the context was created
+    StaleSlab { slab_index: u64 },
+    /// A buffer was used after its context was sent or its engine stopped
+    BufferReleased(String),
+    /// Memory can't be handed to the engine while views into it are still exported
+    ViewsInUse(usize),
+    /// A response or publish payload could not be decoded
+    Decode(String),
+    /// The engine's protocol version or shared struct layout differs from ours
+    ProtocolMismatch(String),
+    /// Any other failure inside the SDK
+    Other(String),
+    /// Another error, prefixed with what the SDK was doing when it happened
+    Context {
+        context: String,
+        source: Box<SdkError>,
+    },
+}
+
+impl SdkError {
+    /// Wraps the error with a description of the operation that failed
+    pub fn context(self, context: impl Into<String>) -> SdkError {
+        SdkError::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// The innermost error, which decides the Python exception type
+    fn root(&self) -> &SdkError {
+        match self {
+            SdkError::Context { source, .. } => source.root(),
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for SdkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdkError::NotStarted => write!(f, "Engine not started"),
+            SdkError::ProcessSpawn(msg) => write!(f, "Failed to launch engine: {}", msg),
+            SdkError::EngineExited(msg) => write!(f, "Engine exited during startup: {}", msg),
+            SdkError::Ipc(msg) => write!(f, "iceoryx2 setup failed: {}", msg),
+            SdkError::ServiceDiscoveryTimeout(service) => {
+                write!(f, "Timed out waiting for engine service '{}'", service)
+            }
+            SdkError::ShmLoan(msg) => write!(f, "SHM loan failed: {}", msg),
+            SdkError::ShmSend(msg) => write!(f, "Send failed: {}", msg),
+            SdkError::Transport(msg) => write!(f, "Engine transport error: {}", msg),
+            SdkError::Rejected(msg) => write!(f, "Engine rejected command: {}", msg),
+            SdkError::Timeout(timeout) => {
+                write!(f, "Engine did not respond within {:.3}s", timeout.as_secs_f64())
+            }
+            SdkError::Cancelled => write!(f, "Command was cancelled"),
+            SdkError::SlabMapping { handle, reason } => {
+                write!(f, "Failed to map slab '{}': {}", handle, reason)
+            }
+            SdkError::AssetPtrOutOfBounds {
+                slab_index,
+                offset,
+                reason,
+            } => write!(
+                f,
+                "AssetPtr (slab {}, offset {}) is out of bounds: {}",
+                slab_index, offset, reason
+            ),
+            SdkError::CorruptAsset {
+                slab_index,
+                offset,
+                reason,
+            } => write!(
+                f,
+                "AssetMeta at slab {}, offset {} is corrupt: {}",
+                slab_index, offset, reason
+            ),
+            SdkError::StaleSlab { slab_index } => write!(
+                f,
+                "Memory slab {} was unmapped or replaced since this context was created",
+                slab_index
+            ),
+            SdkError::BufferReleased(reason) => {
+                write!(f, "Buffer is no longer valid: {}", reason)
+            }
+            SdkError::ViewsInUse(count) => write!(
+                f,
+                "{} view(s) into this context are still exported (e.g. by numpy arrays); \
+                 delete them before sending",
+                count
+            ),
+            SdkError::Decode(msg) => write!(f, "Buffer read error: {}", msg),
+            SdkError::ProtocolMismatch(msg) => write!(f, "Engine protocol mismatch: {}", msg),
+            SdkError::Other(msg) => write!(f, "{}", msg),
+            SdkError::Context { context, source } => write!(f, "{}: {}", context, source),
+        }
+    }
+}
+
+impl std::error::Error for SdkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SdkError::Context { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for SdkError {
+    fn from(msg: String) -> Self {
+        SdkError::Other(msg)
+    }
+}
+
+impl From<SdkError> for PyErr {
+    fn from(err: SdkError) -> PyErr {
+        let msg = err.to_string();
+        match err.root() {
+            SdkError::NotStarted | SdkError::ProcessSpawn(_) | SdkError::EngineExited(_) => {
+                EngineNotStartedError::new_err(msg)
+            }
+            SdkError::Ipc(_)
+            | SdkError::ServiceDiscoveryTimeout(_)
+            | SdkError::ShmLoan(_)
+            | SdkError::ShmSend(_)
+            | SdkError::Transport(_) => EngineTransportError::new_err(msg),
+            SdkError::Rejected(_) => EngineCommandRejected::new_err(msg),
+            SdkError::Timeout(_) => EngineTimeoutError::new_err(msg),
+            SdkError::Cancelled => EngineCommandCancelled::new_err(msg),
+            SdkError::ProtocolMismatch(_) => EngineProtocolError::new_err(msg),
+            SdkError::AssetPtrOutOfBounds { .. }
+            | SdkError::CorruptAsset { .. }
+            | SdkError::StaleSlab { .. }
+            | SdkError::BufferReleased(_)
+            | SdkError::ViewsInUse(_) => EngineMemoryError::new_err(msg),
+            SdkError::SlabMapping { .. }
+            | SdkError::Decode(_)
+            | SdkError::Other(_)
+            | SdkError::Context { .. } => EngineError::new_err(msg),
+        }
+    }
+}
+
+/// Registers the exception hierarchy on the extension module
+pub fn register_exceptions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("EngineError", py.get_type::<EngineError>())?;
+    m.add("EngineNotStartedError", py.get_type::<EngineNotStartedError>())?;
+    m.add("EngineTransportError", py.get_type::<EngineTransportError>())?;
+    m.add("EngineCommandRejected", py.get_type::<EngineCommandRejected>())?;
+    m.add("EngineTimeoutError", py.get_type::<EngineTimeoutError>())?;
+    m.add("EngineCommandCancelled", py.get_type::<EngineCommandCancelled>())?;
+    m.add("EngineProtocolError", py.get_type::<EngineProtocolError>())?;
+    m.add("EngineMemoryError", py.get_type::<EngineMemoryError>())?;
+    Ok(())
+}
@@ -1,8 +1,30 @@
+#[cfg(feature = "numpy")]
+mod asset_arrays;
+mod asset_buffer;
+mod asset_names;
 mod asset_sync_context;
+mod asset_validation;
+mod asset_view;
+mod asyncio_future;
+mod command_handle;
+mod command_options;
 mod command_thread;
+mod engine;
 mod engine_api;
 mod engine_client; // This line remains unchanged
+mod engine_launch;
+mod engine_logs;
+mod error;
+mod mesh_batch_builder;
+mod mesh_queue;
+mod mesh_subscription;
 mod mesh_sync_thread;
+mod protocol;
+mod sdk_config;
+mod sdk_log;
+mod service_names;
+mod slab_table;
+mod supervisor;
 mod tbo_export_context;
 extern crate iceoryx2_loggers;
 
@@ -11,22 +33,204 @@ use pyo3::prelude::*;
 #[pymodule(name = "_elbo_sdk_rust")]
 mod elbo_sdk_rust {
     use crate::asset_sync_context::AssetSyncContext;
-    use crate::engine_api;
+    use crate::command_handle::CommandHandle;
+    use crate::command_options::{
+        CancellationToken, CommandOptions, CommandScope, duration_from_secs,
+    };
+    use crate::engine::Engine;
+    use crate::engine_api::{self, CLIENT};
+    use crate::engine_launch::EngineLaunchConfig;
+    use crate::engine_logs::DEFAULT_TAIL_LINES;
+    use crate::error;
+    use crate::mesh_batch_builder::MeshBatchBuilder;
+    use crate::mesh_queue::{DEFAULT_MESH_QUEUE_CAPACITY, MeshQueuePolicy, MeshQueueStats};
+    use crate::mesh_subscription::{self, MeshSubscription, MeshUpdates};
+    use crate::protocol;
+    use crate::sdk_config::SdkConfig;
+    use crate::sdk_log;
+    use crate::supervisor::{
+        DEFAULT_INITIAL_BACKOFF, DEFAULT_MAX_BACKOFF, DEFAULT_MAX_RESTARTS, EngineState,
+        StopOutcome,
+    };
     use crate::tbo_export_context::TboExportContext;
     use pivot_com_types::fields::Uuid;
     use pyo3::prelude::*;
+    use std::collections::HashMap;
     use std::path::PathBuf;
+    use std::time::Duration;
 
+    const ATEXIT_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Launches the engine and blocks until its services are up and the handshake succeeded.
+    ///
+    /// Args:
+    ///     timeout: Seconds to wait for the engine to become ready (default 30)
+    ///     config: Optional EngineLaunchConfig (args, env, cwd, log capture)
     #[pyfunction]
-    fn start_engine(py: Python) -> PyResult<()> {
-        engine_api::start_engine()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+    #[pyo3(signature = (timeout=None, config=None))]
+    fn start_engine(
+        py: Python,
+        timeout: Option<f64>,
+        config: Option<EngineLaunchConfig>,
+    ) -> PyResult<()> {
+        default_engine().start(py, timeout, config)
     }
 
+    /// Attaches to an already running engine instead of spawning one.
+    ///
+    /// stop_engine() then only detaches; the engine keeps running.
+    ///
+    /// Args:
+    ///     timeout: Seconds to wait for the engine services and handshake (default 30)
     #[pyfunction]
-    fn stop_engine(py: Python) -> PyResult<()> {
-        engine_api::stop_engine()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+    #[pyo3(signature = (timeout=None))]
+    fn connect_engine(py: Python, timeout: Option<f64>) -> PyResult<()> {
+        default_engine().connect(py, timeout)
+    }
+
+    /// True if the SDK spawned the current engine, False if attached, None if not connected.
+    #[pyfunction]
+    fn owns_engine() -> Option<bool> {
+        default_engine().owns_engine()
+    }
+
+    /// The last `n` lines the engine wrote to stdout/stderr, oldest first.
+    #[pyfunction]
+    #[pyo3(signature = (n=DEFAULT_TAIL_LINES))]
+    fn engine_logs(n: usize) -> Vec<String> {
+        default_engine().logs(n)
+    }
+
+    /// Stops the engine: drains queued commands and mesh updates, sends the stop command,
+    /// then escalates to SIGTERM and SIGKILL if it hasn't exited within `timeout` seconds.
+    ///
+    /// Args:
+    ///     timeout: Seconds the whole stop may take before escalating (default 10)
+    ///     drain: Let queued commands finish and mesh updates be polled first (up to half the timeout)
+    ///
+    /// Returns:
+    ///     StopOutcome naming the step that ended the engine
+    #[pyfunction]
+    #[pyo3(signature = (timeout=None, drain=true))]
+    fn stop_engine(py: Python, timeout: Option<f64>, drain: bool) -> PyResult<StopOutcome> {
+        default_engine().stop(py, timeout, drain)
+    }
+
+    /// Registered with atexit so no spawned engine outlives the interpreter.
+    #[pyfunction]
+    fn _stop_all_engines(py: Python) {
+        py.detach(|| {
+            mesh_subscription::stop_all_subscriptions();
+            engine_api::stop_all_engines(ATEXIT_STOP_TIMEOUT);
+        });
+    }
+
+    /// Sets the service prefix and iceoryx2 node settings used by the next start_engine()/connect_engine().
+    #[pyfunction]
+    fn set_sdk_config(config: SdkConfig) -> PyResult<()> {
+        default_engine().set_config(config)
+    }
+
+    #[pyfunction]
+    fn get_sdk_config() -> SdkConfig {
+        default_engine().config()
+    }
+
+    /// Sets the default timeout in seconds for every engine command (None = wait forever).
+    #[pyfunction]
+    #[pyo3(signature = (seconds=None))]
+    fn set_command_timeout(seconds: Option<f64>) -> PyResult<()> {
+        default_engine().set_command_timeout(seconds)
+    }
+
+    #[pyfunction]
+    fn get_command_timeout() -> Option<f64> {
+        default_engine().get_command_timeout()
+    }
+
+    /// Sets how many commands may be awaiting an engine response at once (applies on next start).
+    #[pyfunction]
+    fn set_max_in_flight(max_in_flight: usize) {
+        default_engine().set_max_in_flight(max_in_flight)
+    }
+
+    /// Bounds the queue of mesh updates waiting to be polled (applies on next start).
+    ///
+    /// Args:
+    ///     capacity: Updates held before `policy` applies
+    ///     policy: What happens to an update arriving while the queue is full; the default,
+    ///         Block, never discards one
+    #[pyfunction]
+    #[pyo3(signature = (capacity=DEFAULT_MESH_QUEUE_CAPACITY, policy=MeshQueuePolicy::Block))]
+    fn set_mesh_queue(capacity: usize, policy: MeshQueuePolicy) -> PyResult<()> {
+        default_engine().set_mesh_queue(capacity, policy)
+    }
+
+    /// Dropped, coalesced and queued mesh update counts of the default engine.
+    #[pyfunction]
+    fn mesh_queue_stats() -> MeshQueueStats {
+        default_engine().mesh_queue_stats()
+    }
+
+    /// Applies a timeout and/or cancellation token to commands issued inside the `with` block.
+    #[pyfunction]
+    #[pyo3(signature = (timeout=None, cancel=None))]
+    fn command_scope(
+        timeout: Option<f64>,
+        cancel: Option<PyRef<'_, CancellationToken>>,
+    ) -> PyResult<CommandScope> {
+        Ok(CommandScope::new(CommandOptions {
+            timeout: duration_from_secs(timeout)?,
+            cancel: cancel.map(|c| c.token.clone()),
+        }))
+    }
+
+    #[pyfunction]
+    fn engine_state() -> EngineState {
+        default_engine().state()
+    }
+
+    /// Registers `callback(state: EngineState, detail: Optional[str])`, called on every state change.
+    /// The callback may run on a background thread; pass None to remove it.
+    #[pyfunction]
+    #[pyo3(signature = (callback=None))]
+    fn set_engine_state_callback(callback: Option<Py<PyAny>>) {
+        default_engine().set_state_callback(callback)
+    }
+
+    /// The Engine driven by the module-level functions.
+    #[pyfunction]
+    fn default_engine() -> Engine {
+        Engine::from_client(CLIENT.clone())
+    }
+
+    /// Restart a crashed engine up to `max_restarts` times in a row, doubling the delay from
+    /// `initial_backoff` up to `max_backoff` seconds. An engine that then runs for a minute
+    /// gets a fresh set of restarts. `max_restarts=0` disables restarts.
+    #[pyfunction]
+    #[pyo3(signature = (
+        max_restarts=DEFAULT_MAX_RESTARTS,
+        initial_backoff=DEFAULT_INITIAL_BACKOFF.as_secs_f64(),
+        max_backoff=DEFAULT_MAX_BACKOFF.as_secs_f64(),
+    ))]
+    fn set_restart_policy(
+        max_restarts: u32,
+        initial_backoff: f64,
+        max_backoff: f64,
+    ) -> PyResult<()> {
+        default_engine().set_restart_policy(max_restarts, initial_backoff, max_backoff)
+    }
+
+    /// Version string of the running engine, or None if no engine is connected.
+    #[pyfunction]
+    fn engine_version() -> Option<String> {
+        default_engine().version()
+    }
+
+    /// Capability bit flags (CAP_*) negotiated with the running engine, 0 if none is connected.
+    #[pyfunction]
+    fn engine_capabilities() -> u64 {
+        default_engine().capabilities()
     }
 
     #[pyfunction]
@@ -34,42 +238,36 @@ mod elbo_sdk_rust {
         py: Python,
         uuids: Vec<Uuid>,
         surface_contexts: Vec<u32>,
-    ) -> () {
-        engine_api::standardize_synced_groups_command(uuids, surface_contexts)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    ) -> PyResult<()> {
+        default_engine().standardize_synced_groups_command(py, uuids, surface_contexts)
     }
 
     #[pyfunction]
     fn set_surface_types_command(
         py: Python,
         group_surface_map: std::collections::HashMap<Uuid, i64>,
-    ) -> () {
-        engine_api::set_surface_types_command(group_surface_map)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    ) -> PyResult<()> {
+        default_engine().set_surface_types_command(py, group_surface_map)
     }
 
     #[pyfunction]
-    fn drop_groups_command(py: Python, uuids: Vec<Uuid>) -> () {
-        engine_api::drop_groups_command(uuids)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    fn drop_groups_command(py: Python, uuids: Vec<Uuid>) -> PyResult<()> {
+        default_engine().drop_groups_command(py, uuids)
     }
 
     #[pyfunction]
-    fn get_surface_types_command(py: Python) -> () {
-        engine_api::get_surface_types_command()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    fn get_surface_types_command(py: Python) -> PyResult<HashMap<Vec<u8>, u64>> {
+        default_engine().get_surface_types_command(py)
     }
 
     #[pyfunction]
-    fn organize_objects_command(py: Python) -> () {
-        engine_api::organize_objects_command()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    fn organize_objects_command(py: Python) -> PyResult<()> {
+        default_engine().organize_objects_command(py)
     }
 
     #[pyfunction]
-    fn extract_geometric_features_command(py: Python, uuids: Vec<Uuid>) -> () {
-        engine_api::extract_geometric_features_command(uuids)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    fn extract_geometric_features_command(py: Python, uuids: Vec<Uuid>) -> PyResult<()> {
+        default_engine().extract_geometric_features_command(py, uuids)
     }
 
     #[pyfunction]
@@ -84,22 +282,70 @@ mod elbo_sdk_rust {
     }
 
     #[pyfunction]
-    fn poll_mesh_sync() -> PyResult<Option<AssetSyncContext>> {
-        let context = match engine_api::poll_mesh_sync() {
-            Ok(Some(slices)) => slices,
-            Ok(None) => return Ok(None),
-            Err(e) => {
-                return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                    e.to_string(),
-                ));
-            }
-        };
+    fn poll_mesh_sync(py: Python) -> PyResult<Option<AssetSyncContext>> {
+        default_engine().poll_mesh_sync(py)
+    }
+
+    /// Move objects without resending their geometry.
+    ///
+    /// Args:
+    ///     uuids: Object UUIDs
+    ///     matrices: float32 4x4 matrix per object (buffer or sequence of numbers)
+    ///
+    /// Raises:
+    ///     EngineProtocolError if the engine doesn't support CAP_TRANSFORM_UPDATES
+    #[pyfunction]
+    fn send_transforms(py: Python, uuids: Vec<Uuid>, matrices: &Bound<'_, PyAny>) -> PyResult<()> {
+        default_engine().send_transforms(py, uuids, matrices)
+    }
+
+    /// Drain queued mesh updates into one context, keeping the newest copy of each asset.
+    ///
+    /// Args:
+    ///     max: Most updates to take (None = every queued update)
+    ///
+    /// Returns None if no update is queued.
+    #[pyfunction]
+    #[pyo3(signature = (max=None))]
+    fn poll_mesh_sync_all(py: Python, max: Option<usize>) -> PyResult<Option<AssetSyncContext>> {
+        default_engine().poll_mesh_sync_all(py, max)
+    }
+
+    /// Block until the next mesh update arrives.
+    ///
+    /// Args:
+    ///     timeout: Seconds to wait (None = until an update arrives)
+    ///
+    /// Returns None if `timeout` elapses first.
+    #[pyfunction]
+    #[pyo3(signature = (timeout=None))]
+    fn wait_mesh_sync(py: Python, timeout: Option<f64>) -> PyResult<Option<AssetSyncContext>> {
+        default_engine().wait_mesh_sync(py, timeout)
+    }
+
+    /// Call `callback(context)` on a background thread for every mesh update.
+    /// Keep the returned handle; dropping it or calling unsubscribe() stops delivery.
+    #[pyfunction]
+    fn subscribe_mesh_updates(callback: Py<PyAny>) -> PyResult<MeshSubscription> {
+        default_engine().subscribe_mesh_updates(callback)
+    }
 
-        Ok(Some(context))
+    /// Iterate over mesh updates with `for` or `async for`.
+    ///
+    /// Args:
+    ///     timeout: End iteration after this many seconds without an update (None = never)
+    ///
+    /// Raises:
+    ///     EngineNotStartedError when no engine is connected
+    #[pyfunction]
+    #[pyo3(signature = (timeout=None))]
+    fn mesh_updates(timeout: Option<f64>) -> PyResult<MeshUpdates> {
+        default_engine().mesh_updates(timeout)
     }
 
     #[pyfunction]
     fn prepare_mesh_send(
+        py: Python,
         vert_counts: Vec<u32>,
         edge_counts: Vec<u32>,
         loop_counts: Vec<u32>,
@@ -109,7 +355,8 @@ mod elbo_sdk_rust {
         surface_contexts: Vec<u16>,
         asset_uuids: Vec<Uuid>,
     ) -> PyResult<AssetSyncContext> {
-        let context = engine_api::allocate_memory(
+        default_engine().prepare_mesh_send(
+            py,
             vert_counts,
             edge_counts,
             loop_counts,
@@ -119,15 +366,11 @@ mod elbo_sdk_rust {
             surface_contexts,
             asset_uuids,
         )
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
-
-        Ok(context)
     }
 
     #[pyfunction]
-    fn standardize_groups_command(py: Python, uuids: Vec<Uuid>) -> () {
-        engine_api::standardize_groups_command(uuids)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    fn standardize_groups_command(py: Python, uuids: Vec<Uuid>) -> PyResult<()> {
+        default_engine().standardize_groups_command(py, uuids)
     }
 
     #[pyfunction]
@@ -146,15 +389,13 @@ mod elbo_sdk_rust {
         path: String,
         target_bytes: u64,
         uuids: Vec<Uuid>,
-    ) -> () {
-        engine_api::export_assets_command(&path, target_bytes, uuids)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    ) -> PyResult<()> {
+        default_engine().export_assets_command(py, path, target_bytes, uuids)
     }
 
     #[pyfunction]
-    fn export_all_command(py: Python, path: String, target_bytes: u64) -> () {
-        engine_api::export_all_command(&path, target_bytes)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    fn export_all_command(py: Python, path: String, target_bytes: u64) -> PyResult<()> {
+        default_engine().export_all_command(py, path, target_bytes)
     }
 
     #[pyfunction]
@@ -164,9 +405,8 @@ mod elbo_sdk_rust {
         target_bytes: u64,
         flags: u32,
         uuids: Vec<Uuid>,
-    ) -> () {
-        engine_api::export_mesh_tbo_command(&path, target_bytes, flags, uuids)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    ) -> PyResult<()> {
+        default_engine().export_mesh_tbo_command(py, path, target_bytes, flags, uuids)
     }
 
     #[pyfunction]
@@ -175,30 +415,22 @@ mod elbo_sdk_rust {
         path: String,
         target_bytes: u64,
         uuids: Vec<Uuid>,
-    ) -> () {
-        engine_api::export_asset_tbo_command(&path, target_bytes, uuids)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    ) -> PyResult<()> {
+        default_engine().export_asset_tbo_command(py, path, target_bytes, uuids)
     }
 
     #[pyfunction]
     fn export_all_asset_tbo_command(
+        py: Python,
         path: String,
         target_bytes: u64,
     ) -> PyResult<Vec<String>> {
-        let resp = engine_api::export_all_asset_tbo_command(&path, target_bytes)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e))?;
-        let filenames = resp.read_tbo_flush()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                format!("Failed to read flush response: {}", e),
-            ))?;
-        let result: Vec<String> = filenames.into_iter().map(|s| s.to_string()).collect();
-        Ok(result)
+        default_engine().export_all_asset_tbo_command(py, path, target_bytes)
     }
 
     #[pyfunction]
-    fn drop_all_groups_command(py: Python) -> () {
-        engine_api::drop_all_groups_command()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    fn drop_all_groups_command(py: Python) -> PyResult<()> {
+        default_engine().drop_all_groups_command(py)
     }
 
     #[pyfunction]
@@ -208,32 +440,89 @@ mod elbo_sdk_rust {
         target_bytes: u64,
         flags: u32,
         target_point_count: u32,
-    ) -> () {
-        engine_api::export_all_tbo_command(&path, target_bytes, flags, target_point_count)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    ) -> PyResult<()> {
+        default_engine().export_all_tbo_command(py, path, target_bytes, flags, target_point_count)
     }
 
     #[pyfunction]
-    fn import_assets_command(py: Python, paths: Vec<String>) -> () {
-        engine_api::import_assets_command(paths)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    fn import_assets_command(py: Python, paths: Vec<String>) -> PyResult<()> {
+        default_engine().import_assets_command(py, paths)
     }
 
     #[pymodule_init]
     fn pyinit(m: &Bound<'_, PyModule>) -> PyResult<()> {
+        sdk_log::init();
         m.add_class::<TboExportContext>()?;
+        m.add_class::<MeshBatchBuilder>()?;
+        m.add_class::<MeshQueuePolicy>()?;
+        m.add_class::<MeshQueueStats>()?;
+        m.add_class::<MeshSubscription>()?;
+        m.add_class::<MeshUpdates>()?;
+        m.add_class::<CancellationToken>()?;
+        m.add_class::<CommandScope>()?;
+        m.add_class::<CommandHandle>()?;
+        m.add_class::<EngineState>()?;
+        m.add_class::<StopOutcome>()?;
+        m.add_class::<EngineLaunchConfig>()?;
+        m.add_class::<Engine>()?;
+        m.add_class::<SdkConfig>()?;
+        error::register_exceptions(m)?;
+        m.add("PROTOCOL_VERSION", protocol::PROTOCOL_VERSION)?;
+        m.add("CAP_RESPONSE_EVENTS", protocol::CAP_RESPONSE_EVENTS)?;
+        m.add("CAP_TRANSFORM_UPDATES", protocol::CAP_TRANSFORM_UPDATES)?;
+        m.add("CAP_SLAB_GENERATIONS", protocol::CAP_SLAB_GENERATIONS)?;
+        m.add("CAP_ASSET_MAGIC", protocol::CAP_ASSET_MAGIC)?;
+        m.py()
+            .import("atexit")?
+            .call_method1("register", (m.getattr("_stop_all_engines")?,))?;
         Ok(())
     }
 
     #[pyfunction]
-    fn group_all_objects_command(py: Python) -> () {
-        engine_api::group_all_objects_command()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    fn group_all_objects_command(py: Python) -> PyResult<()> {
+        default_engine().group_all_objects_command(py)
+    }
+
+    #[pyfunction]
+    fn embed_all_assets_command(py: Python) -> PyResult<()> {
+        default_engine().embed_all_assets_command(py)
+    }
+
+    #[pyfunction]
+    fn organize_objects_command_async() -> PyResult<CommandHandle> {
+        default_engine().organize_objects_command_async()
+    }
+
+    #[pyfunction]
+    fn import_assets_command_async(paths: Vec<String>) -> PyResult<CommandHandle> {
+        default_engine().import_assets_command_async(paths)
+    }
+
+    #[pyfunction]
+    fn export_all_command_async(path: String, target_bytes: u64) -> PyResult<CommandHandle> {
+        default_engine().export_all_command_async(path, target_bytes)
+    }
+
+    #[pyfunction]
+    fn export_all_asset_tbo_command_async(
+        path: String,
+        target_bytes: u64,
+    ) -> PyResult<CommandHandle> {
+        default_engine().export_all_asset_tbo_command_async(path, target_bytes)
+    }
+
+    #[pyfunction]
+    fn export_all_tbo_command_async(
+        path: String,
+        target_bytes: u64,
+        flags: u32,
+        target_point_count: u32,
+    ) -> PyResult<CommandHandle> {
+        default_engine().export_all_tbo_command_async(path, target_bytes, flags, target_point_count)
     }
 
     #[pyfunction]
-    fn embed_all_assets_command(py: Python) -> () {
-        engine_api::embed_all_assets_command()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+    fn embed_all_assets_command_async() -> PyResult<CommandHandle> {
+        default_engine().embed_all_assets_command_async()
     }
 }
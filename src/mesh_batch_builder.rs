@@ -0,0 +1,467 @@
+//! Mesh Batch Builder - fills and sends an AssetSyncContext from per-asset arrays.
+//!
+//! Collects each asset's geometry, derives the counts `AssetMeta::new` needs, allocates
+//! engine memory for the whole batch and copies every array into its slice. Array inputs
+//! may be anything exposing the buffer protocol with the right element type (e.g. NumPy
+//! arrays) or plain Python sequences.
+
+use pyo3::buffer::{Element, PyBuffer};
+use pyo3::conversion::FromPyObjectOwned;
+use pyo3::prelude::*;
+use std::sync::Arc;
+
+use pivot_com_types::fields::Uuid;
+
+use crate::asset_sync_context::AssetSyncContext;
+use crate::engine::Engine;
+use crate::engine_api;
+use crate::engine_client::EngineClient;
+use crate::error::SdkError;
+
+/// One asset waiting to be allocated
+struct PendingAsset {
+    group_name: String,
+    surface_context: u16,
+    uuid: Uuid,
+    verts: Vec<f32>,
+    edges: Vec<u32>,
+    loops: Vec<u32>,
+    loop_bases: Vec<u32>,
+    object_loop_counts: Vec<u32>,
+    transforms: Vec<f32>,
+    vert_counts: Vec<u32>,
+    edge_counts: Vec<u32>,
+    object_names: Vec<String>,
+    object_uuids: Vec<Uuid>,
+}
+
+impl PendingAsset {
+    fn object_count(&self) -> usize {
+        self.vert_counts.len()
+    }
+
+    /// Checks that the per-object arrays agree with each other and with the geometry
+    fn validate(&self) -> Result<(), SdkError> {
+        let invalid = |msg: String| {
+            Err(SdkError::Other(format!(
+                "Asset {:?}: {}",
+                self.group_name, msg
+            )))
+        };
+        let objects = self.object_count();
+
+        if self.verts.len() % 3 != 0 {
+            return invalid(format!(
+                "verts has {} floats, not a multiple of 3",
+                self.verts.len()
+            ));
+        }
+        if self.edges.len() % 2 != 0 {
+            return invalid(format!(
+                "edges has {} indices, not a multiple of 2",
+                self.edges.len()
+            ));
+        }
+
+        let per_object = [
+            ("edge_counts", self.edge_counts.len()),
+            ("object_loop_counts", self.object_loop_counts.len()),
+            ("object_names", self.object_names.len()),
+            ("object_uuids", self.object_uuids.len()),
+        ];
+        for (name, len) in per_object {
+            if len != objects {
+                return invalid(format!(
+                    "{} has {} entries for {} objects",
+                    name, len, objects
+                ));
+            }
+        }
+        if self.transforms.len() != objects * 16 {
+            return invalid(format!(
+                "transforms has {} floats, expected {} (4x4 per object)",
+                self.transforms.len(),
+                objects * 16
+            ));
+        }
+
+        let sums = [
+            (
+                "vert_counts",
+                &self.vert_counts,
+                self.verts.len() / 3,
+                "verts",
+            ),
+            (
+                "edge_counts",
+                &self.edge_counts,
+                self.edges.len() / 2,
+                "edges",
+            ),
+            (
+                "object_loop_counts",
+                &self.object_loop_counts,
+                self.loop_bases.len(),
+                "loops",
+            ),
+        ];
+        for (name, counts, total, what) in sums {
+            let sum: u64 = counts.iter().map(|&c| c as u64).sum();
+            if sum != total as u64 {
+                return invalid(format!(
+                    "{} sums to {} but the asset has {} {}",
+                    name, sum, total, what
+                ));
+            }
+        }
+
+        if let Some(&bad) = self
+            .loop_bases
+            .iter()
+            .find(|&&base| base as usize > self.loops.len())
+        {
+            return invalid(format!(
+                "loop_bases entry {} points past the {} loop indices",
+                bad,
+                self.loops.len()
+            ));
+        }
+        if let Some(i) = self.loop_bases.windows(2).position(|w| w[0] > w[1]) {
+            return invalid(format!(
+                "loop_bases decreases from {} to {} at index {}",
+                self.loop_bases[i],
+                self.loop_bases[i + 1],
+                i + 1
+            ));
+        }
+
+        let vertex_count = self.verts.len() / 3;
+        for (name, indices) in [("edges", &self.edges), ("loops", &self.loops)] {
+            if let Some(&bad) = indices.iter().find(|&&v| v as usize >= vertex_count) {
+                return invalid(format!(
+                    "{} references vertex {} but the asset has {} vertices",
+                    name, bad, vertex_count
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds a batch of meshes and sends it in one allocation.
+///
+/// Example:
+///     batch = MeshBatchBuilder()
+///     batch.add_asset("Chair", verts, edges, loops, loop_bases, object_loop_counts,
+///                     vert_counts, edge_counts, transforms, ["Seat", "Back"])
+///     batch.send()
+#[pyclass]
+pub struct MeshBatchBuilder {
+    client: Arc<EngineClient>,
+    assets: Vec<PendingAsset>,
+}
+
+#[pymethods]
+impl MeshBatchBuilder {
+    #[new]
+    #[pyo3(signature = (engine=None))]
+    fn new(engine: Option<PyRef<'_, Engine>>) -> Self {
+        Self {
+            client: match engine {
+                Some(engine) => engine.client.clone(),
+                None => engine_api::CLIENT.clone(),
+            },
+            assets: Vec::new(),
+        }
+    }
+
+    /// Queue one asset. Returns its index in the batch.
+    ///
+    /// Args:
+    ///     group_name: Asset group name
+    ///     verts: float32 positions, 3 per vertex
+    ///     edges: uint32 vertex index pairs
+    ///     loops: uint32 vertex indices of every loop, back to back
+    ///     loop_bases: uint32 start of each loop in `loops`
+    ///     object_loop_counts: Number of loops per object
+    ///     vert_counts: Number of vertices per object
+    ///     edge_counts: Number of edges per object
+    ///     transforms: float32 4x4 matrix per object
+    ///     object_names: One name per object
+    ///     object_uuids: One UUID per object (None = generate)
+    ///     surface_context: Surface context of the asset
+    ///     uuid: Asset UUID (None = generate)
+    #[pyo3(signature = (
+        group_name,
+        verts,
+        edges,
+        loops,
+        loop_bases,
+        object_loop_counts,
+        vert_counts,
+        edge_counts,
+        transforms,
+        object_names,
+        object_uuids=None,
+        surface_context=0,
+        uuid=None,
+    ))]
+    fn add_asset(
+        &mut self,
+        group_name: String,
+        verts: &Bound<'_, PyAny>,
+        edges: &Bound<'_, PyAny>,
+        loops: &Bound<'_, PyAny>,
+        loop_bases: &Bound<'_, PyAny>,
+        object_loop_counts: &Bound<'_, PyAny>,
+        vert_counts: &Bound<'_, PyAny>,
+        edge_counts: &Bound<'_, PyAny>,
+        transforms: &Bound<'_, PyAny>,
+        object_names: Vec<String>,
+        object_uuids: Option<Vec<Uuid>>,
+        surface_context: u16,
+        uuid: Option<Uuid>,
+    ) -> PyResult<usize> {
+        let vert_counts = extract_array::<u32>(vert_counts, "vert_counts")?;
+        let object_uuids =
+            object_uuids.unwrap_or_else(|| vert_counts.iter().map(|_| new_uuid()).collect());
+
+        let asset = PendingAsset {
+            group_name,
+            surface_context,
+            uuid: uuid.unwrap_or_else(new_uuid),
+            verts: extract_array(verts, "verts")?,
+            edges: extract_array(edges, "edges")?,
+            loops: extract_array(loops, "loops")?,
+            loop_bases: extract_array(loop_bases, "loop_bases")?,
+            object_loop_counts: extract_array(object_loop_counts, "object_loop_counts")?,
+            transforms: extract_array(transforms, "transforms")?,
+            vert_counts,
+            edge_counts: extract_array(edge_counts, "edge_counts")?,
+            object_names,
+            object_uuids,
+        };
+        asset.validate()?;
+
+        self.assets.push(asset);
+        Ok(self.assets.len() - 1)
+    }
+
+    fn __len__(&self) -> usize {
+        self.assets.len()
+    }
+
+    /// Drop every queued asset
+    fn clear(&mut self) {
+        self.assets.clear();
+    }
+
+    /// Allocate engine memory for the batch and fill it, without sending.
+    /// The batch is emptied once that succeeded; the returned context can still be edited
+    /// before send().
+    fn build(&mut self, py: Python) -> PyResult<AssetSyncContext> {
+        let context = self.fill(py)?;
+        self.assets.clear();
+        Ok(context)
+    }
+
+    /// Allocate, fill and send the batch in one step. The batch is only emptied once the
+    /// engine accepted it, so a failed send can be retried
+    fn send(&mut self, py: Python) -> PyResult<()> {
+        let mut context = self.fill(py)?;
+        context.send(py)?;
+        self.assets.clear();
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("MeshBatchBuilder(assets={})", self.assets.len())
+    }
+}
+
+impl MeshBatchBuilder {
+    /// A context holding a copy of every queued asset; the batch itself is left untouched
+    fn fill(&self, py: Python) -> PyResult<AssetSyncContext> {
+        if self.assets.is_empty() {
+            return Err(SdkError::Other("MeshBatchBuilder has no assets".to_string()).into());
+        }
+        let client = &self.client;
+        let assets = &self.assets;
+        Ok(py.detach(|| allocate_and_fill(client, assets))?)
+    }
+}
+
+/// Allocates engine memory for `assets` and copies each one into its slices
+fn allocate_and_fill(
+    client: &Arc<EngineClient>,
+    assets: &[PendingAsset],
+) -> Result<AssetSyncContext, SdkError> {
+    let mut context = engine_api::allocate_memory(
+        client,
+        assets.iter().map(|a| (a.verts.len() / 3) as u32).collect(),
+        assets.iter().map(|a| (a.edges.len() / 2) as u32).collect(),
+        assets.iter().map(|a| a.loop_bases.len() as u32).collect(),
+        assets.iter().map(|a| a.loops.len() as u32).collect(),
+        assets.iter().map(|a| a.object_count() as u32).collect(),
+        assets.iter().map(|a| a.group_name.clone()).collect(),
+        assets.iter().map(|a| a.surface_context).collect(),
+        assets.iter().map(|a| a.uuid).collect(),
+    )?;
+
+    for (i, asset) in assets.iter().enumerate() {
+        let object_uuids: Vec<u8> = asset
+            .object_uuids
+            .iter()
+            .flat_map(|uuid| uuid.bytes)
+            .collect();
+
+        context.write_slice(i, 0, &object_uuids)?;
+        context.write_slice(i, 1, as_bytes(&asset.verts))?;
+        context.write_slice(i, 2, as_bytes(&asset.edges))?;
+        context.write_slice(i, 3, as_bytes(&asset.loops))?;
+        context.write_slice(i, 4, as_bytes(&asset.loop_bases))?;
+        context.write_slice(i, 5, as_bytes(&asset.object_loop_counts))?;
+        context.write_slice(i, 6, as_bytes(&asset.transforms))?;
+        context.write_slice(i, 7, as_bytes(&asset.vert_counts))?;
+        context.write_slice(i, 8, as_bytes(&asset.edge_counts))?;
+        context.write_object_names(i, &asset.object_names)?;
+        // Embeddings are computed by the engine; don't hand it whatever the slab held before
+        context.clear_slice(i, 10)?;
+    }
+
+    Ok(context)
+}
+
+fn new_uuid() -> Uuid {
+    Uuid {
+        bytes: engine_api::generate_uuid_bytes(),
+    }
+}
+
+/// Copies a buffer-protocol object of `T`, or any sequence of numbers, into a Vec
+pub fn extract_array<'py, T>(obj: &Bound<'py, PyAny>, name: &str) -> PyResult<Vec<T>>
+where
+    T: Element + FromPyObjectOwned<'py>,
+{
+    match PyBuffer::<T>::get(obj) {
+        Ok(buffer) => buffer.to_vec(obj.py()),
+        Err(_) => obj.extract::<Vec<T>>().map_err(|e| {
+            SdkError::Other(format!(
+                "{} must be a buffer of {} or a sequence of numbers: {}",
+                name,
+                std::any::type_name::<T>(),
+                e
+            ))
+            .into()
+        }),
+    }
+}
+
+fn as_bytes<T: Copy>(values: &[T]) -> &[u8] {
+    unsafe {
+        std::slice::from_raw_parts(values.as_ptr() as *const u8, std::mem::size_of_val(values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two objects holding one triangle each
+    fn two_triangles() -> PendingAsset {
+        PendingAsset {
+            group_name: "Pair".to_string(),
+            surface_context: 0,
+            uuid: Uuid {
+                bytes: [1; Uuid::SIZE],
+            },
+            verts: vec![0.0; 18],
+            edges: vec![0, 1, 1, 2, 2, 0, 3, 4, 4, 5, 5, 3],
+            loops: vec![0, 1, 2, 3, 4, 5],
+            loop_bases: vec![0, 3],
+            object_loop_counts: vec![1, 1],
+            transforms: vec![0.0; 32],
+            vert_counts: vec![3, 3],
+            edge_counts: vec![3, 3],
+            object_names: vec!["Left".to_string(), "Right".to_string()],
+            object_uuids: vec![
+                Uuid {
+                    bytes: [2; Uuid::SIZE],
+                },
+                Uuid {
+                    bytes: [3; Uuid::SIZE],
+                },
+            ],
+        }
+    }
+
+    fn rejects(asset: PendingAsset, fragment: &str) {
+        match asset.validate() {
+            Err(SdkError::Other(msg)) => assert!(msg.contains(fragment), "{}", msg),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn consistent_asset_is_valid() {
+        assert!(two_triangles().validate().is_ok());
+    }
+
+    #[test]
+    fn flat_arrays_must_hold_whole_elements() {
+        let mut asset = two_triangles();
+        asset.verts.pop();
+        rejects(asset, "verts");
+
+        let mut asset = two_triangles();
+        asset.edges.pop();
+        rejects(asset, "edges");
+    }
+
+    #[test]
+    fn per_object_arrays_must_match_the_object_count() {
+        let mut asset = two_triangles();
+        asset.object_names.pop();
+        rejects(asset, "object_names");
+
+        let mut asset = two_triangles();
+        asset.transforms.truncate(16);
+        rejects(asset, "transforms");
+    }
+
+    #[test]
+    fn counts_must_sum_to_the_geometry() {
+        let mut asset = two_triangles();
+        asset.vert_counts = vec![3, 2];
+        rejects(asset, "vert_counts");
+
+        let mut asset = two_triangles();
+        asset.object_loop_counts = vec![2, 1];
+        rejects(asset, "object_loop_counts");
+    }
+
+    #[test]
+    fn loop_bases_must_stay_in_range() {
+        let mut asset = two_triangles();
+        asset.loop_bases = vec![0, 7];
+        rejects(asset, "points past");
+    }
+
+    #[test]
+    fn loop_bases_must_not_decrease() {
+        let mut asset = two_triangles();
+        asset.loop_bases = vec![3, 0];
+        rejects(asset, "decreases");
+    }
+
+    #[test]
+    fn indices_must_reference_existing_vertices() {
+        let mut asset = two_triangles();
+        asset.edges[1] = 6;
+        rejects(asset, "edges references vertex 6");
+
+        let mut asset = two_triangles();
+        asset.loops[5] = 9;
+        rejects(asset, "loops references vertex 9");
+    }
+}
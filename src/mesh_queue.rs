@@ -0,0 +1,447 @@
+//! Mesh Queue - bounded hand-off of mesh updates from the mesh sync thread.
+//!
+//! Every MeshPublish points into engine memory that the engine reuses, so updates are not
+//! allowed to pile up. Once `capacity` updates are waiting, the policy decides what happens
+//! to the next one: by default the mesh thread waits for room, and the opt-in policies that
+//! discard updates log every one they drop. The counters are kept per client and survive
+//! engine restarts.
+
+use crossbeam::channel::RecvTimeoutError;
+use pivot_com_types::MeshPublish;
+use pivot_com_types::fields::Uuid;
+use pyo3::prelude::*;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_MESH_QUEUE_CAPACITY: usize = 64;
+/// How often a blocked producer checks whether its session is shutting down
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What the SDK does with a mesh update that arrives while the queue is full.
+#[pyclass(eq, eq_int)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MeshQueuePolicy {
+    /// Stop receiving until the consumer catches up; the engine sees the back-pressure
+    #[default]
+    Block,
+    /// Discard the oldest queued update
+    DropOldest,
+    /// Discard queued updates whose assets are all in the new one; if that frees nothing,
+    /// discard the oldest. Finding the assets maps the update's slabs and validates its
+    /// headers on the mesh thread, before the update is queued
+    CoalesceByUuid,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MeshQueueConfig {
+    pub capacity: usize,
+    pub policy: MeshQueuePolicy,
+}
+
+impl Default for MeshQueueConfig {
+    fn default() -> Self {
+        MeshQueueConfig {
+            capacity: DEFAULT_MESH_QUEUE_CAPACITY,
+            policy: MeshQueuePolicy::default(),
+        }
+    }
+}
+
+/// Running totals over every session of one client
+#[derive(Debug, Default)]
+pub struct MeshQueueCounters {
+    received: AtomicU64,
+    dropped: AtomicU64,
+    coalesced: AtomicU64,
+    blocked: AtomicU64,
+}
+
+/// Counters of the mesh update queue.
+///
+/// Attributes:
+///     capacity: Updates the queue holds before the policy applies
+///     policy: MeshQueuePolicy in effect
+///     queued: Updates waiting to be polled right now
+///     received: Updates received from the engine
+///     dropped: Updates discarded because the queue was full
+///     coalesced: Updates discarded because a newer one covered all of their assets
+///     blocked: Times the mesh thread had to wait for room (Block policy)
+#[pyclass(frozen, get_all)]
+#[derive(Debug, Clone)]
+pub struct MeshQueueStats {
+    pub capacity: usize,
+    pub policy: MeshQueuePolicy,
+    pub queued: usize,
+    pub received: u64,
+    pub dropped: u64,
+    pub coalesced: u64,
+    pub blocked: u64,
+}
+
+#[pymethods]
+impl MeshQueueStats {
+    fn __repr__(&self) -> String {
+        format!(
+            "MeshQueueStats(capacity={}, policy={:?}, queued={}, received={}, dropped={}, coalesced={}, blocked={})",
+            self.capacity,
+            self.policy,
+            self.queued,
+            self.received,
+            self.dropped,
+            self.coalesced,
+            self.blocked
+        )
+    }
+}
+
+/// Resolves the asset UUIDs a publish refers to; None if they can't be read yet
+pub type UuidResolver = Box<dyn Fn(&MeshPublish) -> Option<Vec<Uuid>> + Send>;
+
+struct Entry {
+    publish: MeshPublish,
+    uuids: Option<Vec<Uuid>>,
+}
+
+impl Entry {
+    /// Whether every asset of this entry is also in `uuids`
+    fn covered_by(&self, uuids: &[Uuid]) -> bool {
+        self.uuids.as_ref().is_some_and(|own| {
+            own.iter()
+                .all(|uuid| uuids.iter().any(|other| other.bytes == uuid.bytes))
+        })
+    }
+}
+
+/// Consumer side of a session's mesh updates
+pub struct MeshQueue {
+    config: MeshQueueConfig,
+    entries: Mutex<VecDeque<Entry>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    /// Set once the producer is gone; remaining entries can still be taken
+    closed: AtomicBool,
+    counters: Arc<MeshQueueCounters>,
+}
+
+impl std::fmt::Debug for MeshQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MeshQueue")
+            .field("config", &self.config)
+            .field("queued", &self.len())
+            .finish()
+    }
+}
+
+impl MeshQueue {
+    /// Creates a queue and the sender the mesh sync thread pushes into
+    pub fn new(
+        config: MeshQueueConfig,
+        counters: Arc<MeshQueueCounters>,
+        resolver: UuidResolver,
+    ) -> (MeshSender, Arc<MeshQueue>) {
+        let config = MeshQueueConfig {
+            capacity: config.capacity.max(1),
+            ..config
+        };
+        let queue = Arc::new(MeshQueue {
+            config,
+            entries: Mutex::new(VecDeque::with_capacity(config.capacity)),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            closed: AtomicBool::new(false),
+            counters,
+        });
+        let sender = MeshSender {
+            queue: queue.clone(),
+            resolver,
+        };
+        (sender, queue)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn try_recv(&self) -> Option<MeshPublish> {
+        let publish = self.entries.lock().unwrap().pop_front()?.publish;
+        self.not_full.notify_one();
+        Some(publish)
+    }
+
+    /// Puts back an update a consumer took but could not deliver, ahead of the others. The
+    /// policy is skipped, so the queue may briefly hold one update over its capacity
+    pub fn requeue(&self, publish: MeshPublish) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.push_front(Entry {
+            publish,
+            uuids: None,
+        });
+        drop(entries);
+        self.not_empty.notify_all();
+    }
+
+    /// Waits for the next update until `deadline`. Disconnected once the producer is gone
+    /// and the queue is empty
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<MeshPublish, RecvTimeoutError> {
+        let mut entries = self.entries.lock().unwrap();
+        loop {
+            if let Some(entry) = entries.pop_front() {
+                self.not_full.notify_one();
+                return Ok(entry.publish);
+            }
+            if self.closed.load(Ordering::Acquire) {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            entries = self.not_empty.wait_timeout(entries, remaining).unwrap().0;
+        }
+    }
+
+    pub fn stats(&self) -> MeshQueueStats {
+        stats(self.config, self.len(), &self.counters)
+    }
+}
+
+/// Stats for a client without a session: its configured queue and running totals
+pub fn idle_stats(config: MeshQueueConfig, counters: &MeshQueueCounters) -> MeshQueueStats {
+    stats(config, 0, counters)
+}
+
+fn stats(config: MeshQueueConfig, queued: usize, counters: &MeshQueueCounters) -> MeshQueueStats {
+    MeshQueueStats {
+        capacity: config.capacity,
+        policy: config.policy,
+        queued,
+        received: counters.received.load(Ordering::Relaxed),
+        dropped: counters.dropped.load(Ordering::Relaxed),
+        coalesced: counters.coalesced.load(Ordering::Relaxed),
+        blocked: counters.blocked.load(Ordering::Relaxed),
+    }
+}
+
+/// Producer side, owned by the mesh sync thread; dropping it disconnects the queue
+pub struct MeshSender {
+    queue: Arc<MeshQueue>,
+    resolver: UuidResolver,
+}
+
+impl MeshSender {
+    /// Queues `publish`, applying the policy if the queue is full. Under Block this waits
+    /// for room and gives up (returning false) once `shutdown` is set
+    pub fn send(&self, publish: MeshPublish, shutdown: &AtomicBool) -> bool {
+        let queue = &*self.queue;
+        let counters = &queue.counters;
+        counters.received.fetch_add(1, Ordering::Relaxed);
+
+        let uuids = match queue.config.policy {
+            MeshQueuePolicy::CoalesceByUuid => (self.resolver)(&publish),
+            _ => None,
+        };
+
+        let mut entries = queue.entries.lock().unwrap();
+        if let Some(uuids) = &uuids {
+            let before = entries.len();
+            entries.retain(|entry| !entry.covered_by(uuids));
+            counters
+                .coalesced
+                .fetch_add((before - entries.len()) as u64, Ordering::Relaxed);
+        }
+
+        if entries.len() >= queue.config.capacity {
+            match queue.config.policy {
+                MeshQueuePolicy::Block => {
+                    counters.blocked.fetch_add(1, Ordering::Relaxed);
+                    while entries.len() >= queue.config.capacity {
+                        if shutdown.load(Ordering::Relaxed) {
+                            counters.dropped.fetch_add(1, Ordering::Relaxed);
+                            log::warn!("Dropped a mesh update that arrived while shutting down");
+                            return false;
+                        }
+                        entries = queue
+                            .not_full
+                            .wait_timeout(entries, BLOCK_POLL_INTERVAL)
+                            .unwrap()
+                            .0;
+                    }
+                }
+                MeshQueuePolicy::DropOldest | MeshQueuePolicy::CoalesceByUuid => {
+                    while entries.len() >= queue.config.capacity {
+                        entries.pop_front();
+                        counters.dropped.fetch_add(1, Ordering::Relaxed);
+                        log::warn!(
+                            "Mesh update queue is full ({} updates), dropped the oldest",
+                            queue.config.capacity
+                        );
+                    }
+                }
+            }
+        }
+
+        entries.push_back(Entry { publish, uuids });
+        drop(entries);
+        queue.not_empty.notify_all();
+        true
+    }
+}
+
+impl Drop for MeshSender {
+    fn drop(&mut self) {
+        self.queue.closed.store(true, Ordering::Release);
+        // Take the lock so a waiter can't miss the wake-up between its check and its wait
+        let _entries = self.queue.entries.lock().unwrap();
+        self.queue.not_empty.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A publish told apart from the others by the first byte of its root slab handle
+    fn publish(tag: u8) -> MeshPublish {
+        // MeshPublish is plain shared memory data, so all zeroes is a valid value
+        let mut publish: MeshPublish = unsafe { std::mem::zeroed() };
+        publish.header.root_slab_handle[0] = tag;
+        publish
+    }
+
+    fn tag(publish: &MeshPublish) -> u8 {
+        publish.header.root_slab_handle[0]
+    }
+
+    fn uuid(byte: u8) -> Uuid {
+        Uuid {
+            bytes: [byte; Uuid::SIZE],
+        }
+    }
+
+    /// A queue whose resolver maps each publish tag to the given assets
+    fn queue(
+        capacity: usize,
+        policy: MeshQueuePolicy,
+        assets: Vec<(u8, Vec<Uuid>)>,
+    ) -> (MeshSender, Arc<MeshQueue>) {
+        let resolver: UuidResolver = Box::new(move |publish| {
+            assets
+                .iter()
+                .find(|(t, _)| *t == tag(publish))
+                .map(|(_, uuids)| uuids.clone())
+        });
+        MeshQueue::new(
+            MeshQueueConfig { capacity, policy },
+            Arc::default(),
+            resolver,
+        )
+    }
+
+    fn drain(queue: &MeshQueue) -> Vec<u8> {
+        std::iter::from_fn(|| queue.try_recv())
+            .map(|p| tag(&p))
+            .collect()
+    }
+
+    #[test]
+    fn drop_oldest_discards_the_oldest_update() {
+        let (sender, queue) = queue(2, MeshQueuePolicy::DropOldest, Vec::new());
+        let shutdown = AtomicBool::new(false);
+        for t in 1..=3 {
+            assert!(sender.send(publish(t), &shutdown));
+        }
+
+        let stats = queue.stats();
+        assert_eq!((stats.queued, stats.received, stats.dropped), (2, 3, 1));
+        assert_eq!(drain(&queue), [2, 3]);
+    }
+
+    #[test]
+    fn coalesce_discards_updates_covered_by_a_newer_one() {
+        let assets = vec![
+            (1, vec![uuid(1)]),
+            (2, vec![uuid(2)]),
+            (3, vec![uuid(1), uuid(2)]),
+        ];
+        let (sender, queue) = queue(4, MeshQueuePolicy::CoalesceByUuid, assets);
+        let shutdown = AtomicBool::new(false);
+        for t in 1..=3 {
+            assert!(sender.send(publish(t), &shutdown));
+        }
+
+        let stats = queue.stats();
+        assert_eq!((stats.coalesced, stats.dropped), (2, 0));
+        assert_eq!(drain(&queue), [3]);
+    }
+
+    #[test]
+    fn coalesce_falls_back_to_dropping_the_oldest() {
+        let assets = vec![(1, vec![uuid(1)]), (2, vec![uuid(2)])];
+        let (sender, queue) = queue(1, MeshQueuePolicy::CoalesceByUuid, assets);
+        let shutdown = AtomicBool::new(false);
+        assert!(sender.send(publish(1), &shutdown));
+        assert!(sender.send(publish(2), &shutdown));
+
+        let stats = queue.stats();
+        assert_eq!((stats.coalesced, stats.dropped), (0, 1));
+        assert_eq!(drain(&queue), [2]);
+    }
+
+    #[test]
+    fn block_waits_for_room() {
+        let (sender, queue) = queue(1, MeshQueuePolicy::Block, Vec::new());
+        let shutdown = Arc::new(AtomicBool::new(false));
+        assert!(sender.send(publish(1), &shutdown));
+
+        let flag = shutdown.clone();
+        let producer = std::thread::spawn(move || sender.send(publish(2), &flag));
+        while queue.stats().blocked == 0 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(queue.try_recv().map(|p| tag(&p)), Some(1));
+
+        assert!(producer.join().unwrap());
+        let stats = queue.stats();
+        assert_eq!((stats.blocked, stats.dropped), (1, 0));
+        assert_eq!(drain(&queue), [2]);
+    }
+
+    #[test]
+    fn block_gives_up_on_shutdown() {
+        let (sender, queue) = queue(1, MeshQueuePolicy::Block, Vec::new());
+        assert!(sender.send(publish(1), &AtomicBool::new(false)));
+        assert!(!sender.send(publish(2), &AtomicBool::new(true)));
+
+        let stats = queue.stats();
+        assert_eq!((stats.received, stats.blocked, stats.dropped), (2, 1, 1));
+        assert_eq!(drain(&queue), [1]);
+    }
+
+    #[test]
+    fn requeued_update_is_received_first() {
+        let (sender, queue) = queue(2, MeshQueuePolicy::DropOldest, Vec::new());
+        let shutdown = AtomicBool::new(false);
+        assert!(sender.send(publish(1), &shutdown));
+        assert!(sender.send(publish(2), &shutdown));
+
+        let first = queue.try_recv().unwrap();
+        queue.requeue(first);
+        assert_eq!(drain(&queue), [1, 2]);
+    }
+
+    #[test]
+    fn queue_disconnects_once_drained_after_the_sender_is_gone() {
+        let (sender, queue) = queue(2, MeshQueuePolicy::DropOldest, Vec::new());
+        assert!(sender.send(publish(1), &AtomicBool::new(false)));
+        drop(sender);
+
+        let deadline = Instant::now() + Duration::from_secs(1);
+        assert_eq!(queue.recv_deadline(deadline).map(|p| tag(&p)), Ok(1));
+        assert_eq!(
+            queue.recv_deadline(deadline).map(|p| tag(&p)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+}
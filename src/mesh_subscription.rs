@@ -0,0 +1,297 @@
+//! Mesh Subscription - push and iterator delivery of mesh updates.
+//!
+//! Every consumer reads the same mesh queue, so an update is delivered to exactly one
+//! of poll_mesh_sync(), wait_mesh_sync(), an iterator or a subscription. Blocking waits run
+//! without the GIL in short slices so Ctrl+C still interrupts them; `async for` hands its
+//! futures to one helper thread per iterator, which resolves them as updates arrive. An
+//! update whose task was cancelled before it could be delivered goes back to the queue.
+
+use crossbeam::channel::{self, Receiver, Sender};
+use pivot_com_types::MeshPublish;
+use pyo3::exceptions::PyStopAsyncIteration;
+use pyo3::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock, Weak};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::asset_sync_context::AssetSyncContext;
+use crate::asyncio_future::PendingFuture;
+use crate::command_options::deadline_after;
+use crate::engine_api;
+use crate::engine_client::EngineClient;
+use crate::error::SdkError;
+use crate::mesh_queue::MeshQueue;
+
+/// Longest stretch a wait spends without the GIL before checking for signals
+const WAIT_SLICE: Duration = Duration::from_millis(100);
+/// How often a subscription retries while no engine is connected
+const IDLE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Stop flags of every live subscription, so their threads can be ended at interpreter exit
+static SUBSCRIPTIONS: LazyLock<Mutex<Vec<Weak<Dispatcher>>>> =
+    LazyLock::new(|| Mutex::new(Vec::new()));
+
+/// Waits up to `timeout` (None = forever) for the next mesh update, checking for Python
+/// signals between slices
+pub fn wait_for_update(
+    py: Python,
+    client: &Arc<EngineClient>,
+    timeout: Option<Duration>,
+) -> PyResult<Option<AssetSyncContext>> {
+    let deadline = timeout.map(deadline_after);
+    loop {
+        let slice = match deadline {
+            Some(deadline) => deadline
+                .saturating_duration_since(Instant::now())
+                .min(WAIT_SLICE),
+            None => WAIT_SLICE,
+        };
+        if let Some(context) = py.detach(|| engine_api::wait_mesh_sync(client, slice))? {
+            return Ok(Some(context));
+        }
+        py.check_signals()?;
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Ok(None);
+        }
+    }
+}
+
+/// Stops every subscription's dispatcher thread; called at interpreter exit without the GIL
+pub fn stop_all_subscriptions() {
+    let dispatchers: Vec<Arc<Dispatcher>> = SUBSCRIPTIONS
+        .lock()
+        .unwrap()
+        .drain(..)
+        .filter_map(|d| d.upgrade())
+        .collect();
+
+    for dispatcher in dispatchers {
+        dispatcher.stop();
+    }
+}
+
+/// Background thread delivering mesh updates to one callback
+struct Dispatcher {
+    stopped: AtomicBool,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Dispatcher {
+    /// Signals the thread and waits for it, unless called from the callback itself
+    fn stop(&self) {
+        self.stopped.store(true, Ordering::Release);
+        let handle = self.thread.lock().unwrap().take();
+        if let Some(handle) = handle
+            && handle.thread().id() != std::thread::current().id()
+        {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn dispatch_loop(client: Arc<EngineClient>, callback: Py<PyAny>, dispatcher: Weak<Dispatcher>) {
+    loop {
+        match dispatcher.upgrade() {
+            Some(d) if !d.stopped.load(Ordering::Acquire) => {}
+            _ => return,
+        }
+
+        match engine_api::wait_mesh_sync(&client, WAIT_SLICE) {
+            Ok(Some(context)) => Python::attach(|py| {
+                // A raising callback is reported like an exception in a thread; the
+                // subscription keeps running
+                if let Err(e) = callback.call1(py, (context,)) {
+                    e.write_unraisable(py, Some(callback.bind(py)));
+                }
+            }),
+            Ok(None) => {}
+            Err(SdkError::NotStarted) => std::thread::sleep(IDLE_INTERVAL),
+            Err(e) => Python::attach(|py| {
+                PyErr::from(e).write_unraisable(py, Some(callback.bind(py)));
+            }),
+        }
+    }
+}
+
+/// Handle to a callback registered with subscribe_mesh_updates().
+///
+/// The callback runs on a dispatcher thread with the GIL held, once per update. It keeps
+/// running across engine restarts until unsubscribe() is called or the handle is dropped.
+/// Usable as a context manager.
+#[pyclass(frozen)]
+pub struct MeshSubscription {
+    dispatcher: Arc<Dispatcher>,
+}
+
+impl MeshSubscription {
+    pub fn start(client: Arc<EngineClient>, callback: Py<PyAny>) -> PyResult<Self> {
+        let dispatcher = Arc::new(Dispatcher {
+            stopped: AtomicBool::new(false),
+            thread: Mutex::new(None),
+        });
+
+        let weak = Arc::downgrade(&dispatcher);
+        let handle = std::thread::Builder::new()
+            .name("elbo-mesh-subscription".to_string())
+            .spawn(move || dispatch_loop(client, callback, weak))
+            .map_err(|e| SdkError::Other(format!("Failed to start mesh subscription: {}", e)))?;
+        *dispatcher.thread.lock().unwrap() = Some(handle);
+
+        let mut subscriptions = SUBSCRIPTIONS.lock().unwrap();
+        subscriptions.retain(|d| d.strong_count() > 0);
+        subscriptions.push(Arc::downgrade(&dispatcher));
+
+        Ok(MeshSubscription { dispatcher })
+    }
+}
+
+impl Drop for MeshSubscription {
+    fn drop(&mut self) {
+        // Don't join here: the dispatcher may be waiting for the GIL this thread holds
+        self.dispatcher.stopped.store(true, Ordering::Release);
+    }
+}
+
+#[pymethods]
+impl MeshSubscription {
+    /// Stop delivering updates. Waits for a callback in progress to return.
+    fn unsubscribe(&self, py: Python) {
+        let dispatcher = &self.dispatcher;
+        py.detach(|| dispatcher.stop());
+    }
+
+    #[getter]
+    fn active(&self) -> bool {
+        !self.dispatcher.stopped.load(Ordering::Acquire)
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&self, py: Python, _args: &Bound<'_, pyo3::types::PyTuple>) {
+        self.unsubscribe(py);
+    }
+
+    fn __repr__(&self) -> String {
+        format!("MeshSubscription(active={})", self.active())
+    }
+}
+
+/// One `__anext__` waiting for the next update
+struct AsyncRequest {
+    future: PendingFuture,
+    deadline: Option<Instant>,
+}
+
+/// Serves the `__anext__` futures of one iterator in order, until the iterator is dropped
+fn serve_async(client: Arc<EngineClient>, requests: Receiver<AsyncRequest>) {
+    for request in requests {
+        let outcome = wait_async(&client, &request);
+        Python::attach(|py| match outcome {
+            Ok(Some((publish, queue, context))) => {
+                let context = Py::new(py, context).map(Py::into_any);
+                request
+                    .future
+                    .resolve(py, context, move || queue.requeue(publish));
+            }
+            Ok(None) => {
+                let done = Err(PyStopAsyncIteration::new_err(()));
+                request.future.resolve(py, done, || {});
+            }
+            Err(e) => request.future.resolve(py, Err(e.into()), || {}),
+        });
+    }
+}
+
+/// Waits for the update `request` asks for, giving up once its task is cancelled or its
+/// deadline passes. Returns the publish and its queue along with the context, so the update
+/// can be put back if the task is cancelled before the context reaches it
+fn wait_async(
+    client: &Arc<EngineClient>,
+    request: &AsyncRequest,
+) -> Result<Option<(MeshPublish, Arc<MeshQueue>, AssetSyncContext)>, SdkError> {
+    let deadline = request.deadline;
+    while !request.future.cancelled().load(Ordering::SeqCst) {
+        let slice = match deadline {
+            Some(deadline) => deadline
+                .saturating_duration_since(Instant::now())
+                .min(WAIT_SLICE),
+            None => WAIT_SLICE,
+        };
+        if let Some((publish, queue)) = client.wait_mesh_update(slice)? {
+            let context = engine_api::context_from_publish(client, &publish)?;
+            return Ok(Some((publish, queue, context)));
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            break;
+        }
+    }
+    Ok(None)
+}
+
+/// Iterator over mesh updates, usable with both `for` and `async for`.
+///
+/// Iteration ends once no update arrives within `timeout` seconds (None = never). Raises
+/// EngineNotStartedError when no engine is connected, including while waiting.
+#[pyclass]
+pub struct MeshUpdates {
+    client: Arc<EngineClient>,
+    timeout: Option<Duration>,
+    /// Feeds the helper thread started by the first `__anext__`
+    async_requests: OnceLock<Sender<AsyncRequest>>,
+}
+
+impl MeshUpdates {
+    pub fn new(client: Arc<EngineClient>, timeout: Option<Duration>) -> Self {
+        MeshUpdates {
+            client,
+            timeout,
+            async_requests: OnceLock::new(),
+        }
+    }
+
+    /// The helper thread's queue, starting the thread on first use
+    fn async_requests(&self) -> PyResult<&Sender<AsyncRequest>> {
+        if let Some(requests) = self.async_requests.get() {
+            return Ok(requests);
+        }
+        let (sender, receiver) = channel::unbounded();
+        let client = self.client.clone();
+        std::thread::Builder::new()
+            .name("elbo-mesh-await".to_string())
+            .spawn(move || serve_async(client, receiver))
+            .map_err(|e| SdkError::Other(format!("Failed to start mesh update waiter: {}", e)))?;
+        Ok(self.async_requests.get_or_init(|| sender))
+    }
+}
+
+#[pymethods]
+impl MeshUpdates {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python) -> PyResult<Option<AssetSyncContext>> {
+        wait_for_update(py, &self.client, self.timeout)
+    }
+
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Returns an asyncio future for the next update; cancelling it stops the wait
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let (future, awaitable) = PendingFuture::new(py)?;
+        let request = AsyncRequest {
+            future,
+            deadline: self.timeout.map(deadline_after),
+        };
+        self.async_requests()?
+            .send(request)
+            .map_err(|_| SdkError::Other("Mesh update waiter has stopped".to_string()))?;
+        Ok(awaitable)
+    }
+}
@@ -4,70 +4,118 @@ use std::{
         atomic::{AtomicBool, Ordering},
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
-const MESH_UPDATES_SERVICE_NAME: &str = "PivotEngine/MeshUpdates";
-const NOTIFICATIONS_SERVICE_NAME: &str = "PivotEngine/Notifications";
 
 use crossbeam::channel;
 use iceoryx2::prelude::*;
 use pivot_com_types::MeshPublish;
 
+use crate::error::SdkError;
+use crate::mesh_queue::MeshSender;
+use crate::service_names::ServiceNames;
+
+type MeshPorts = (
+    iceoryx2::port::subscriber::Subscriber<ipc::Service, MeshPublish, ()>,
+    iceoryx2::port::listener::Listener<ipc::Service>,
+);
+
 pub fn spawn_mesh_sync_thread(
     node: Arc<Node<ipc::Service>>,
+    names: Arc<ServiceNames>,
     shutdown: Arc<AtomicBool>,
-    mesh_update_tx: channel::Sender<MeshPublish>,
+    mesh_update_tx: MeshSender,
+    discovery_timeout: Duration,
+    ready_tx: channel::Sender<Result<(), SdkError>>,
 ) -> std::thread::JoinHandle<()> {
     thread::spawn(move || {
         // 1. Create independent ports for this thread
         // This ensures we never compete with send_command for a Mutex.
-
-        let (subscriber, listener) = loop {
-            let sub_service = node
-                .service_builder(&MESH_UPDATES_SERVICE_NAME.try_into().unwrap())
-                .publish_subscribe::<MeshPublish>()
-                .open();
-
-            let event_service = node
-                .service_builder(&NOTIFICATIONS_SERVICE_NAME.try_into().unwrap())
-                .event()
-                .open();
-
-            match (sub_service, event_service) {
-                (Ok(sub), Ok(event)) => {
-                    break (
-                        sub.subscriber_builder().create().expect("Subscriber error"),
-                        event.listener_builder().create().expect("Listener error"),
-                    );
-                }
-                _ => {
-                    // Engine isn't fully ready yet, or services aren't registered.
-                    // Sleep for a bit and try again.
-                    thread::sleep(Duration::from_millis(500));
-                    println!("Waiting for Engine mesh services to appear...");
-                }
+        let (subscriber, listener) = match open_mesh_ports(&node, &names, &shutdown, discovery_timeout) {
+            Ok(ports) => {
+                let _ = ready_tx.send(Ok(()));
+                ports
+            }
+            Err(e) => {
+                log::error!("Mesh sync service unavailable: {}", e);
+                let _ = ready_tx.send(Err(e));
+                return;
             }
         };
 
-        println!("Background mesh sync loop active.");
+        log::debug!("Background mesh sync loop active");
 
         while !shutdown.load(Ordering::Relaxed) {
             // Blocks here until the Engine signals the listener
             let r = listener.timed_wait_all(|_| {}, Duration::from_millis(200));
 
             if r.is_err() {
-                eprintln!("Error while waiting for mesh updates: {:?}", r.err());
+                log::warn!("Error while waiting for mesh updates: {:?}", r.err());
             }
 
             // Drain all pending samples from the subscriber
             while let Ok(Some(sample)) = subscriber.receive() {
-                // Send the mesh update to the main thread or whoever is interested
-                let r = mesh_update_tx.send(*sample.payload());
-                if r.is_err() {
-                    eprintln!("Failed to send mesh update to main thread: {:?}", r.err());
+                // Queue the mesh update for whoever polls; a full queue applies its policy
+                if !mesh_update_tx.send(*sample.payload(), &shutdown) {
+                    break;
                 }
             }
         }
-        println!("Background mesh sync loop exiting.");
+        log::debug!("Background mesh sync loop exiting");
     })
 }
+
+/// Waits for the engine to register its mesh services and creates this thread's ports
+fn open_mesh_ports(
+    node: &Node<ipc::Service>,
+    names: &ServiceNames,
+    shutdown: &AtomicBool,
+    discovery_timeout: Duration,
+) -> Result<MeshPorts, SdkError> {
+    let updates_name: ServiceName = names
+        .mesh_updates
+        .as_str()
+        .try_into()
+        .map_err(|e| SdkError::Ipc(format!("{:?}", e)))?;
+    let notifications_name: ServiceName = names
+        .notifications
+        .as_str()
+        .try_into()
+        .map_err(|e| SdkError::Ipc(format!("{:?}", e)))?;
+    let deadline = Instant::now() + discovery_timeout;
+
+    let (sub_service, event_service) = loop {
+        let sub_service = node
+            .service_builder(&updates_name)
+            .publish_subscribe::<MeshPublish>()
+            .open();
+
+        let event_service = node.service_builder(&notifications_name).event().open();
+
+        match (sub_service, event_service) {
+            (Ok(sub), Ok(event)) => break (sub, event),
+            _ => {
+                if shutdown.load(Ordering::Relaxed) || Instant::now() >= deadline {
+                    return Err(SdkError::ServiceDiscoveryTimeout(
+                        names.mesh_updates.clone(),
+                    ));
+                }
+                // Engine isn't fully ready yet, or services aren't registered.
+                // Sleep for a bit and try again.
+                thread::sleep(Duration::from_millis(500));
+                log::debug!("Waiting for engine mesh services to appear");
+            }
+        }
+    };
+
+    let subscriber = sub_service
+        .subscriber_builder()
+        .create()
+        .map_err(|e| SdkError::Ipc(format!("Subscriber error: {:?}", e)))?;
+    let listener = event_service
+        .listener_builder()
+        .create()
+        .map_err(|e| SdkError::Ipc(format!("Listener error: {:?}", e)))?;
+
+    Ok((subscriber, listener))
+}
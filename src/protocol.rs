@@ -0,0 +1,217 @@
+//! SDK ↔ engine protocol handshake.
+//!
+//! The SDK reinterprets raw shared memory as `pivot-com-types` structs, so both sides
+//! must agree on the protocol version and on the exact layout of every shared struct.
+//!
+//! Protocol version 1 sits behind the `protocol-v1` feature until `pivot-com-types` has
+//! these additions:
+//! - `EngineCommand::handshake` and `EngineResponse::read_handshake`
+//! - `SlabRegistry::slab_generations` (`CAP_SLAB_GENERATIONS`)
+//! - `AssetMeta::{MAGIC, magic, checksum, compute_checksum}` (`CAP_ASSET_MAGIC`)
+//! - `AssetMeta::{group_name_len, offset_*}` for the name and slice bounds checks
+//! - `EngineCommand::send_transforms` and `MeshPublish::header.flags`
+//!   (`CAP_TRANSFORM_UPDATES`)
+//!
+//! Without it no handshake takes place and the engine is treated as a legacy one that
+//! supports none of the capabilities below.
+
+use pivot_com_types::MeshPublish;
+#[cfg(feature = "protocol-v1")]
+use pivot_com_types::{
+    EngineCommand, EngineResponse, alloc::SlabRegistry, asset_meta::AssetMeta, asset_ptr::AssetPtr,
+};
+#[cfg(feature = "protocol-v1")]
+use std::mem::{align_of, size_of};
+
+#[cfg(feature = "protocol-v1")]
+use crate::error::SdkError;
+
+/// Bumped whenever the command set or the meaning of a shared struct changes
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Engine signals finished responses on the response event service
+pub const CAP_RESPONSE_EVENTS: u64 = 1 << 0;
+/// Engine accepts transform-only updates
+pub const CAP_TRANSFORM_UPDATES: u64 = 1 << 1;
+/// Engine tracks slab generations in the SlabRegistry
+pub const CAP_SLAB_GENERATIONS: u64 = 1 << 2;
+/// Engine stamps a magic value and header checksum into every AssetMeta
+pub const CAP_ASSET_MAGIC: u64 = 1 << 3;
+
+/// MeshPublish header flag: only object transforms changed, geometry is untouched
+#[cfg(feature = "protocol-v1")]
+pub const MESH_UPDATE_TRANSFORMS_ONLY: u32 = 1 << 0;
+
+/// Capabilities this SDK build knows how to use
+#[cfg(feature = "protocol-v1")]
+pub const SDK_CAPABILITIES: u64 =
+    CAP_RESPONSE_EVENTS | CAP_TRANSFORM_UPDATES | CAP_SLAB_GENERATIONS | CAP_ASSET_MAGIC;
+
+/// What the engine told us about itself during the handshake
+#[derive(Debug, Clone)]
+pub struct EngineInfo {
+    pub version: String,
+    pub protocol_version: u32,
+    pub capabilities: u64,
+}
+
+impl EngineInfo {
+    /// An engine that predates the handshake: baseline commands only
+    #[cfg(not(feature = "protocol-v1"))]
+    pub fn legacy() -> Self {
+        EngineInfo {
+            version: "unknown".to_string(),
+            protocol_version: 0,
+            capabilities: 0,
+        }
+    }
+
+    pub fn supports(&self, capability: u64) -> bool {
+        self.capabilities & capability == capability
+    }
+
+    /// Whether `publish` only moved objects. Engines without CAP_TRANSFORM_UPDATES always
+    /// resend geometry, so their flags are not trusted
+    #[cfg(feature = "protocol-v1")]
+    pub fn is_transform_update(&self, publish: &MeshPublish) -> bool {
+        self.supports(CAP_TRANSFORM_UPDATES)
+            && publish.header.flags & MESH_UPDATE_TRANSFORMS_ONLY != 0
+    }
+
+    /// The baseline MeshPublish header has no flags, so every update carries geometry
+    #[cfg(not(feature = "protocol-v1"))]
+    pub fn is_transform_update(&self, _publish: &MeshPublish) -> bool {
+        false
+    }
+}
+
+/// Hash over the size and alignment of every struct shared with the engine
+#[cfg(feature = "protocol-v1")]
+pub fn layout_hash() -> u64 {
+    let layout = [
+        size_of::<AssetMeta>(),
+        align_of::<AssetMeta>(),
+        size_of::<AssetPtr>(),
+        align_of::<AssetPtr>(),
+        size_of::<SlabRegistry>(),
+        align_of::<SlabRegistry>(),
+        size_of::<MeshPublish>(),
+        align_of::<MeshPublish>(),
+        size_of::<EngineCommand>(),
+        align_of::<EngineCommand>(),
+        size_of::<EngineResponse>(),
+        align_of::<EngineResponse>(),
+    ];
+    fingerprint(&layout)
+}
+
+/// FNV-1a over each value as little-endian u64; the engine hashes its layout the same way
+#[cfg(feature = "protocol-v1")]
+fn fingerprint(layout: &[usize]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &value in layout {
+        for byte in (value as u64).to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+#[cfg(feature = "protocol-v1")]
+pub fn handshake_command() -> EngineCommand {
+    EngineCommand::handshake(PROTOCOL_VERSION, layout_hash(), SDK_CAPABILITIES)
+}
+
+/// Checks the engine's handshake response against what this SDK was compiled with
+#[cfg(feature = "protocol-v1")]
+pub fn verify_handshake(resp: &EngineResponse) -> Result<EngineInfo, SdkError> {
+    let handshake = resp
+        .read_handshake()
+        .map_err(|e| SdkError::Decode(format!("Failed to read handshake response: {}", e)))?;
+
+    check_compatible(handshake.protocol_version, handshake.layout_hash)?;
+
+    Ok(EngineInfo {
+        version: handshake.engine_version.to_string(),
+        protocol_version: handshake.protocol_version,
+        capabilities: handshake.capabilities & SDK_CAPABILITIES,
+    })
+}
+
+/// Rejects an engine that speaks another protocol version or lays out shared structs differently
+#[cfg(feature = "protocol-v1")]
+fn check_compatible(engine_protocol: u32, engine_layout: u64) -> Result<(), SdkError> {
+    if engine_protocol != PROTOCOL_VERSION {
+        return Err(SdkError::ProtocolMismatch(format!(
+            "SDK speaks protocol {}, engine speaks {}",
+            PROTOCOL_VERSION, engine_protocol
+        )));
+    }
+
+    let expected = layout_hash();
+    if engine_layout != expected {
+        return Err(SdkError::ProtocolMismatch(format!(
+            "shared struct layout differs (SDK {:#018x}, engine {:#018x}); rebuild against the same pivot-com-types",
+            expected, engine_layout
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "protocol-v1")]
+    #[test]
+    fn fingerprint_matches_the_engine_algorithm() {
+        assert_eq!(fingerprint(&[]), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fingerprint(&[1]), 0x89cd_3129_1d2a_efa4);
+        let layout = [64, 8, 24, 8, 4096, 8, 512, 8, 1024, 8, 1024, 8];
+        assert_eq!(fingerprint(&layout), 0x27aa_6361_70a5_e217);
+    }
+
+    #[cfg(feature = "protocol-v1")]
+    #[test]
+    fn fingerprint_depends_on_order() {
+        assert_ne!(fingerprint(&[24, 8]), fingerprint(&[8, 24]));
+    }
+
+    #[cfg(feature = "protocol-v1")]
+    #[test]
+    fn matching_engine_is_compatible() {
+        assert!(check_compatible(PROTOCOL_VERSION, layout_hash()).is_ok());
+    }
+
+    #[cfg(feature = "protocol-v1")]
+    #[test]
+    fn other_protocol_version_is_rejected() {
+        assert!(matches!(
+            check_compatible(PROTOCOL_VERSION + 1, layout_hash()),
+            Err(SdkError::ProtocolMismatch(_))
+        ));
+    }
+
+    #[cfg(feature = "protocol-v1")]
+    #[test]
+    fn other_layout_is_rejected() {
+        assert!(matches!(
+            check_compatible(PROTOCOL_VERSION, layout_hash() ^ 1),
+            Err(SdkError::ProtocolMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn supports_needs_every_bit() {
+        let info = EngineInfo {
+            version: "test".to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: CAP_RESPONSE_EVENTS | CAP_SLAB_GENERATIONS,
+        };
+        assert!(info.supports(CAP_SLAB_GENERATIONS));
+        assert!(!info.supports(CAP_TRANSFORM_UPDATES));
+        assert!(!info.supports(CAP_SLAB_GENERATIONS | CAP_TRANSFORM_UPDATES));
+    }
+}
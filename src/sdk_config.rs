@@ -0,0 +1,126 @@
+use iceoryx2::config::Config;
+use iceoryx2::prelude::*;
+use pyo3::prelude::*;
+use std::process::Command;
+
+use crate::error::SdkError;
+use crate::service_names::{DEFAULT_SERVICE_PREFIX, SERVICE_PREFIX_ENV, ServiceNames};
+
+/// Environment variable telling a spawned engine which iceoryx2 domain to join
+pub const DOMAIN_ENV: &str = "PIVOT_IOX2_DOMAIN";
+/// Environment variable telling a spawned engine which iceoryx2 config file to load
+pub const ICEORYX2_CONFIG_ENV: &str = "PIVOT_IOX2_CONFIG";
+
+/// Where the SDK and the engine meet over iceoryx2.
+///
+/// Args:
+///     service_prefix: Namespace of the engine services, e.g. "PivotEngine" → "PivotEngine/CommandService"
+///     node_name: Name of the SDK's iceoryx2 node (None = anonymous)
+///     iceoryx2_config: Path to an iceoryx2 TOML config file (None = iceoryx2 defaults)
+///     domain: iceoryx2 domain; processes in different domains never see each other's services
+#[pyclass(get_all, set_all)]
+#[derive(Debug, Clone)]
+pub struct SdkConfig {
+    pub service_prefix: String,
+    pub node_name: Option<String>,
+    pub iceoryx2_config: Option<String>,
+    pub domain: Option<String>,
+}
+
+impl Default for SdkConfig {
+    fn default() -> Self {
+        SdkConfig {
+            service_prefix: DEFAULT_SERVICE_PREFIX.to_string(),
+            node_name: None,
+            iceoryx2_config: None,
+            domain: None,
+        }
+    }
+}
+
+#[pymethods]
+impl SdkConfig {
+    #[new]
+    #[pyo3(signature = (
+        service_prefix=DEFAULT_SERVICE_PREFIX.to_string(),
+        node_name=None,
+        iceoryx2_config=None,
+        domain=None,
+    ))]
+    fn new(
+        service_prefix: String,
+        node_name: Option<String>,
+        iceoryx2_config: Option<String>,
+        domain: Option<String>,
+    ) -> PyResult<Self> {
+        if service_prefix.is_empty() {
+            return Err(SdkError::Other("service_prefix must not be empty".to_string()).into());
+        }
+        Ok(SdkConfig {
+            service_prefix,
+            node_name,
+            iceoryx2_config,
+            domain,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SdkConfig(service_prefix={:?}, node_name={:?}, iceoryx2_config={:?}, domain={:?})",
+            self.service_prefix, self.node_name, self.iceoryx2_config, self.domain
+        )
+    }
+}
+
+impl SdkConfig {
+    pub fn service_names(&self) -> ServiceNames {
+        ServiceNames::new(&self.service_prefix)
+    }
+
+    /// The iceoryx2 config file (if any) with the domain applied on top
+    fn iceoryx2_config(&self) -> Result<Config, SdkError> {
+        let mut config = match &self.iceoryx2_config {
+            Some(path) => {
+                let file = FilePath::new(path.as_bytes()).map_err(|e| {
+                    SdkError::Ipc(format!("Invalid iceoryx2 config path {}: {:?}", path, e))
+                })?;
+                Config::from_file(&file).map_err(|e| {
+                    SdkError::Ipc(format!("Failed to load iceoryx2 config {}: {:?}", path, e))
+                })?
+            }
+            None => Config::default(),
+        };
+
+        if let Some(domain) = &self.domain {
+            config.global.prefix = FileName::new(domain.as_bytes()).map_err(|e| {
+                SdkError::Ipc(format!("Invalid iceoryx2 domain {}: {:?}", domain, e))
+            })?;
+        }
+        Ok(config)
+    }
+
+    pub fn create_node(&self) -> Result<Node<ipc::Service>, SdkError> {
+        let config = self.iceoryx2_config()?;
+        let mut builder = NodeBuilder::new().config(&config);
+        if let Some(name) = &self.node_name {
+            let name = NodeName::new(name)
+                .map_err(|e| SdkError::Ipc(format!("Invalid node name {}: {:?}", name, e)))?;
+            builder = builder.name(&name);
+        }
+
+        builder
+            .create::<ipc::Service>()
+            .map_err(|e| SdkError::Ipc(format!("Failed to create iceoryx2 node: {:?}", e)))
+    }
+
+    /// Tells a spawned engine to register its services where this SDK will look for them
+    pub fn apply_to(&self, command: &mut Command) {
+        command.env(SERVICE_PREFIX_ENV, &self.service_prefix);
+        if let Some(domain) = &self.domain {
+            command.env(DOMAIN_ENV, domain);
+        }
+        if let Some(path) = &self.iceoryx2_config {
+            command.env(ICEORYX2_CONFIG_ENV, path);
+        }
+    }
+}
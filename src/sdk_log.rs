@@ -0,0 +1,40 @@
+//! Fallback logger for the SDK's own diagnostics.
+//!
+//! Warnings and errors go to stderr, as they did before the SDK used `log`. They are not
+//! forwarded to Python `logging`: records come from background threads that may hold client
+//! locks, and taking the GIL there could deadlock against Python code waiting on those locks.
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Variable picking the level (error, warn, info, debug, trace or off); warn if unset
+const LEVEL_VAR: &str = "ELBO_SDK_LOG";
+
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+            && metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[SDK] {}: {}", record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+/// Installs the stderr logger unless the process already has a logger
+pub fn init() {
+    let level = std::env::var(LEVEL_VAR)
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(LevelFilter::Warn);
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
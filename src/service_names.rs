@@ -0,0 +1,34 @@
+/// Namespace the engine's iceoryx2 services live under, e.g. `PivotEngine/CommandService`
+pub const DEFAULT_SERVICE_PREFIX: &str = "PivotEngine";
+/// Environment variable telling a spawned engine which namespace to register its services in
+pub const SERVICE_PREFIX_ENV: &str = "PIVOT_SERVICE_PREFIX";
+
+/// Names of every iceoryx2 service shared with one engine instance
+#[derive(Debug, Clone)]
+pub struct ServiceNames {
+    pub prefix: String,
+    pub command: String,
+    pub command_events: String,
+    pub command_responses: String,
+    pub mesh_updates: String,
+    pub notifications: String,
+}
+
+impl ServiceNames {
+    pub fn new(prefix: &str) -> Self {
+        ServiceNames {
+            prefix: prefix.to_string(),
+            command: format!("{}/CommandService", prefix),
+            command_events: format!("{}/CommandEvents", prefix),
+            command_responses: format!("{}/ResponseEvents", prefix),
+            mesh_updates: format!("{}/MeshUpdates", prefix),
+            notifications: format!("{}/Notifications", prefix),
+        }
+    }
+}
+
+impl Default for ServiceNames {
+    fn default() -> Self {
+        ServiceNames::new(DEFAULT_SERVICE_PREFIX)
+    }
+}
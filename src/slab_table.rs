@@ -0,0 +1,384 @@
+//! Shared memory slabs mapped from the engine.
+//!
+//! Slot 0 is the root slab, which starts with the engine's `SlabRegistry`; the other slots
+//! follow the registry. Engines with `CAP_SLAB_GENERATIONS` bump a slot's generation whenever
+//! they free or recreate its slab, so a stale mapping is never mistaken for the new one.
+//! Every mapping gets a fresh epoch; contexts remember the epochs their pointers came from
+//! and refuse to expose memory once the slab has been unmapped or replaced. They also share
+//! the mapping itself, so views Python still holds never point at unmapped memory.
+
+use iceoryx2::prelude::*;
+use iceoryx2_bb_posix::file::AccessMode;
+use iceoryx2_bb_posix::shared_memory::{SharedMemory, SharedMemoryBuilder};
+use std::sync::Arc;
+use pivot_com_types::alloc::SlabRegistry;
+
+use crate::asset_validation::SlabBounds;
+use crate::error::SdkError;
+
+/// The mapping an AssetMeta pointer was hydrated from
+#[derive(Debug, Clone)]
+pub struct SlabRef {
+    pub index: usize,
+    pub epoch: u64,
+    /// Keeps the memory mapped after the table lets go of it
+    mapping: Arc<SharedMemory>,
+}
+
+impl SlabRef {
+    /// One past the last byte of the mapping
+    #[cfg(not(feature = "protocol-v1"))]
+    pub fn end(&self) -> *const u8 {
+        unsafe { (self.mapping.base_address().as_ptr() as *const u8).add(self.mapping.size()) }
+    }
+}
+
+#[derive(Debug)]
+struct MappedSlab {
+    shm: Arc<SharedMemory>,
+    handle: Vec<u8>,
+    generation: u64,
+    epoch: u64,
+}
+
+#[derive(Debug)]
+pub struct SlabTable {
+    /// Handle of the root slab the current mappings were discovered from
+    root_handle: Vec<u8>,
+    slots: Vec<Option<MappedSlab>>,
+    next_epoch: u64,
+}
+unsafe impl Send for SlabTable {}
+
+impl Default for SlabTable {
+    fn default() -> Self {
+        SlabTable {
+            root_handle: Vec::new(),
+            slots: Vec::new(),
+            next_epoch: 1,
+        }
+    }
+}
+
+impl SlabTable {
+    /// Unmaps every slab; contexts created before this see their memory as stale
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.root_handle.clear();
+    }
+
+    pub fn is_current(&self, slab: &SlabRef) -> bool {
+        matches!(self.slots.get(slab.index), Some(Some(mapped)) if mapped.epoch == slab.epoch)
+    }
+
+    /// Maps the root slab if `root_handle` differs from the one we know (first use or engine
+    /// restart), then brings the other slots in line with the engine's registry
+    pub fn sync(&mut self, root_handle: &[u8], use_generations: bool) -> Result<(), SdkError> {
+        let root_handle = bytes_to_clean_str(root_handle);
+        if self.root_handle != root_handle || self.slots.first().is_none_or(Option::is_none) {
+            if !self.root_handle.is_empty() {
+                log::info!(
+                    "Root slab changed from {:?} to {:?}, remapping all slabs",
+                    String::from_utf8_lossy(&self.root_handle),
+                    String::from_utf8_lossy(root_handle)
+                );
+            }
+            self.clear();
+            let shm = open_shm(root_handle)?;
+            self.map(0, shm, root_handle, 0);
+            self.root_handle = root_handle.to_vec();
+        }
+
+        self.sync_registry(use_generations);
+        Ok(())
+    }
+
+    /// Unmaps slabs the engine retired and maps the ones it created or recreated
+    fn sync_registry(&mut self, use_generations: bool) {
+        let Some(Some(root)) = self.slots.first() else {
+            return;
+        };
+        if root.shm.size() < std::mem::size_of::<SlabRegistry>() {
+            log::error!("Root slab is too small to hold the slab registry");
+            return;
+        }
+        let registry = unsafe { &*(root.shm.base_address().as_ptr() as *const SlabRegistry) };
+        let target_count = (registry.num_slabs as usize).min(registry.slab_handles.len());
+
+        for index in target_count..self.slots.len() {
+            self.unmap(index, "dropped from the registry");
+        }
+        self.slots.truncate(target_count.max(1));
+
+        for index in 1..target_count {
+            let handle = bytes_to_clean_str(&registry.slab_handles[index]);
+            let generation = if use_generations {
+                registry_generation(registry, index)
+            } else {
+                0
+            };
+
+            if let Some(Some(mapped)) = self.slots.get(index) {
+                if mapped.handle == handle && mapped.generation == generation {
+                    continue;
+                }
+                self.unmap(index, "retired by the engine");
+            }
+
+            // An empty handle marks a slot the engine freed and hasn't reused yet
+            if handle.is_empty() {
+                continue;
+            }
+
+            match open_shm(handle) {
+                Ok(shm) => {
+                    log::debug!(
+                        "Auto-mapped memory slab [{}] (generation {}): {:?}",
+                        index,
+                        generation,
+                        String::from_utf8_lossy(handle)
+                    );
+                    self.map(index, shm, handle, generation);
+                }
+                Err(e) => log::warn!("Failed to map discovered slab: {}", e),
+            }
+        }
+    }
+
+    fn map(&mut self, index: usize, shm: SharedMemory, handle: &[u8], generation: u64) {
+        if self.slots.len() <= index {
+            self.slots.resize_with(index + 1, || None);
+        }
+        self.slots[index] = Some(MappedSlab {
+            shm: Arc::new(shm),
+            handle: handle.to_vec(),
+            generation,
+            epoch: self.next_epoch,
+        });
+        self.next_epoch += 1;
+    }
+
+    fn unmap(&mut self, index: usize, reason: &str) {
+        if let Some(mapped) = self.slots.get_mut(index).and_then(Option::take) {
+            log::debug!(
+                "Unmapped memory slab [{}] ({}): {:?}",
+                index,
+                reason,
+                String::from_utf8_lossy(&mapped.handle)
+            );
+        }
+    }
+
+    /// Bounds of a mapped slab and the reference contexts keep to it
+    pub fn lookup(
+        &self,
+        slab_index: usize,
+        offset: u64,
+    ) -> Result<(SlabBounds, SlabRef), SdkError> {
+        match self.slots.get(slab_index) {
+            Some(Some(mapped)) => Ok((
+                SlabBounds {
+                    index: slab_index,
+                    base: mapped.shm.base_address().as_ptr() as *mut u8,
+                    size: mapped.shm.size(),
+                },
+                SlabRef {
+                    index: slab_index,
+                    epoch: mapped.epoch,
+                    mapping: mapped.shm.clone(),
+                },
+            )),
+            Some(None) => Err(SdkError::AssetPtrOutOfBounds {
+                slab_index: slab_index as u64,
+                offset,
+                reason: "slab is not mapped".to_string(),
+            }),
+            None => Err(SdkError::AssetPtrOutOfBounds {
+                slab_index: slab_index as u64,
+                offset,
+                reason: format!("only {} slabs are known", self.slots.len()),
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "protocol-v1")]
+fn registry_generation(registry: &SlabRegistry, index: usize) -> u64 {
+    registry.slab_generations[index]
+}
+
+/// The baseline registry has no generations; no engine reports CAP_SLAB_GENERATIONS here
+#[cfg(not(feature = "protocol-v1"))]
+fn registry_generation(_registry: &SlabRegistry, _index: usize) -> u64 {
+    0
+}
+
+pub fn bytes_to_clean_str(bytes: &[u8]) -> &[u8] {
+    // Look for the first null terminator, or use the whole slice if none found
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    &bytes[..len]
+}
+
+///Opens existing shm by u8 handle
+fn open_shm(handle: &[u8]) -> Result<SharedMemory, SdkError> {
+    let clean_handle = bytes_to_clean_str(handle);
+    let handle_str = String::from_utf8_lossy(clean_handle).to_string();
+    let file_name = match FileName::new(clean_handle) {
+        Ok(f) => f,
+        Err(e) => {
+            return Err(SdkError::SlabMapping {
+                handle: handle_str,
+                reason: format!("invalid shared memory name: {:?}", e),
+            });
+        }
+    };
+
+    SharedMemoryBuilder::new(&file_name)
+        .open_existing(AccessMode::ReadWrite)
+        .map_err(|e| SdkError::SlabMapping {
+            handle: handle_str,
+            reason: format!("{:?}", e),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iceoryx2_bb_posix::creation_mode::CreationMode;
+    use iceoryx2_bb_posix::permission::Permission;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const SLAB_SIZE: usize = 4096;
+
+    /// A slab owned by the test, removed again when dropped
+    fn create_shm(size: usize) -> (String, SharedMemory) {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "elbo_sdk_test_{}_{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        );
+        let shm = SharedMemoryBuilder::new(&FileName::new(name.as_bytes()).unwrap())
+            .creation_mode(CreationMode::PurgeAndCreate)
+            .size(size)
+            .permission(Permission::OWNER_ALL)
+            .zero_memory(true)
+            .create()
+            .unwrap();
+        (name, shm)
+    }
+
+    fn registry(root: &SharedMemory) -> &mut SlabRegistry {
+        unsafe { &mut *(root.base_address().as_ptr() as *mut SlabRegistry) }
+    }
+
+    /// Publishes `slabs` (handle, generation) in slots 1.. of the root slab's registry
+    fn publish(root: &SharedMemory, slabs: &[(&str, u64)]) {
+        let registry = registry(root);
+        registry.num_slabs = (slabs.len() + 1) as _;
+        for (i, (handle, generation)) in slabs.iter().enumerate() {
+            let slot = &mut registry.slab_handles[i + 1];
+            slot.fill(0);
+            slot[..handle.len()].copy_from_slice(handle.as_bytes());
+            #[cfg(feature = "protocol-v1")]
+            {
+                registry.slab_generations[i + 1] = *generation;
+            }
+            #[cfg(not(feature = "protocol-v1"))]
+            let _ = generation;
+        }
+    }
+
+    fn root() -> (String, SharedMemory) {
+        create_shm(std::mem::size_of::<SlabRegistry>())
+    }
+
+    #[test]
+    fn sync_maps_registered_slabs() {
+        let (root_name, root) = root();
+        let (slab_name, _slab) = create_shm(SLAB_SIZE);
+        publish(&root, &[(&slab_name, 0)]);
+
+        let mut table = SlabTable::default();
+        table.sync(root_name.as_bytes(), true).unwrap();
+
+        let (bounds, slab) = table.lookup(1, 0).unwrap();
+        assert!(bounds.size >= SLAB_SIZE);
+        assert!(table.is_current(&slab));
+        assert!(table.lookup(2, 0).is_err());
+    }
+
+    #[cfg(feature = "protocol-v1")]
+    #[test]
+    fn new_generation_replaces_the_mapping() {
+        let (root_name, root) = root();
+        let (slab_name, _slab) = create_shm(SLAB_SIZE);
+        publish(&root, &[(&slab_name, 1)]);
+
+        let mut table = SlabTable::default();
+        table.sync(root_name.as_bytes(), true).unwrap();
+        let (_, old) = table.lookup(1, 0).unwrap();
+
+        // Same handle, recreated by the engine
+        publish(&root, &[(&slab_name, 2)]);
+        table.sync(root_name.as_bytes(), true).unwrap();
+        let (_, new) = table.lookup(1, 0).unwrap();
+
+        assert!(!table.is_current(&old));
+        assert!(table.is_current(&new));
+        assert!(new.epoch > old.epoch);
+    }
+
+    #[cfg(feature = "protocol-v1")]
+    #[test]
+    fn generations_are_ignored_without_the_capability() {
+        let (root_name, root) = root();
+        let (slab_name, _slab) = create_shm(SLAB_SIZE);
+        publish(&root, &[(&slab_name, 1)]);
+
+        let mut table = SlabTable::default();
+        table.sync(root_name.as_bytes(), false).unwrap();
+        let (_, slab) = table.lookup(1, 0).unwrap();
+
+        publish(&root, &[(&slab_name, 2)]);
+        table.sync(root_name.as_bytes(), false).unwrap();
+        assert!(table.is_current(&slab));
+    }
+
+    #[test]
+    fn freed_slot_is_unmapped() {
+        let (root_name, root) = root();
+        let (slab_name, _slab) = create_shm(SLAB_SIZE);
+        publish(&root, &[(&slab_name, 0)]);
+
+        let mut table = SlabTable::default();
+        table.sync(root_name.as_bytes(), true).unwrap();
+        let (_, slab) = table.lookup(1, 0).unwrap();
+
+        publish(&root, &[("", 0)]);
+        table.sync(root_name.as_bytes(), true).unwrap();
+        assert!(!table.is_current(&slab));
+        assert!(table.lookup(1, 0).is_err());
+    }
+
+    #[test]
+    fn new_root_remaps_everything() {
+        let (slab_name, _slab) = create_shm(SLAB_SIZE);
+        let (first_name, first) = root();
+        publish(&first, &[(&slab_name, 0)]);
+
+        let mut table = SlabTable::default();
+        table.sync(first_name.as_bytes(), true).unwrap();
+        let (_, old_root) = table.lookup(0, 0).unwrap();
+        let (_, old_slab) = table.lookup(1, 0).unwrap();
+
+        // The engine restarted with a new root slab but reuses the slab name
+        let (second_name, second) = root();
+        publish(&second, &[(&slab_name, 0)]);
+        table.sync(second_name.as_bytes(), true).unwrap();
+
+        assert!(!table.is_current(&old_root));
+        assert!(!table.is_current(&old_slab));
+        assert!(table.lookup(1, 0).is_ok());
+    }
+}
@@ -0,0 +1,326 @@
+//! Engine process supervision.
+//!
+//! Watches the spawned `pivot_engine` child, reports lifecycle transitions and
+//! tells the client when the engine died without being asked to.
+
+use pyo3::prelude::*;
+#[cfg(unix)]
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a SIGTERM'd engine gets to exit before it is killed
+const TERM_GRACE: Duration = Duration::from_secs(2);
+
+/// Lifecycle of the engine process as seen by the SDK.
+#[pyclass(eq, eq_int)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EngineState {
+    /// Process spawned, services not yet available
+    Starting,
+    /// Engine is accepting commands
+    Ready,
+    /// Engine exited without being asked to
+    Crashed,
+    /// No engine is running
+    #[default]
+    Stopped,
+}
+
+/// Which step of stop_engine() ended the engine.
+#[pyclass(eq, eq_int)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopOutcome {
+    /// No engine was running
+    NotRunning,
+    /// The engine wasn't ours; we only disconnected from it
+    Detached,
+    /// The engine exited after the stop command
+    Graceful,
+    /// The engine exited after SIGTERM (Unix only)
+    Terminated,
+    /// The engine had to be killed
+    Killed,
+}
+
+pub const DEFAULT_MAX_RESTARTS: u32 = 3;
+pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// An engine that stayed up this long after a restart has recovered
+pub const STABLE_UPTIME: Duration = Duration::from_secs(60);
+
+/// How a crashed engine gets restarted; `max_restarts` caps restarts in a row, 0 disables them
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    pub max_restarts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+/// The policy set_restart_policy() applies when called without arguments
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            max_restarts: DEFAULT_MAX_RESTARTS,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// What a client starts with: crashed engines stay down until a policy is set
+    pub fn disabled() -> Self {
+        RestartPolicy {
+            max_restarts: 0,
+            ..RestartPolicy::default()
+        }
+    }
+
+    /// Exponential backoff before the given (zero based) restart attempt
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(1u32 << attempt.min(16))
+            .min(self.max_backoff)
+    }
+}
+
+pub type StateCallback = Arc<dyn Fn(EngineState, Option<&str>) + Send + Sync>;
+
+/// Current engine state and the observer notified when it changes
+#[derive(Default)]
+pub struct EngineStatus {
+    state: Mutex<EngineState>,
+    callback: Mutex<Option<StateCallback>>,
+}
+
+impl std::fmt::Debug for EngineStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EngineStatus")
+            .field("state", &self.get())
+            .finish()
+    }
+}
+
+impl EngineStatus {
+    pub fn get(&self) -> EngineState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Records a transition and notifies the callback, outside of any lock, if the state changed
+    pub fn set(&self, state: EngineState, detail: Option<&str>) {
+        {
+            let mut current = self.state.lock().unwrap();
+            if *current == state {
+                return;
+            }
+            *current = state;
+        }
+
+        let callback = self.callback.lock().unwrap().clone();
+        if let Some(callback) = callback {
+            callback(state, detail);
+        }
+    }
+
+    pub fn set_callback(&self, callback: Option<StateCallback>) {
+        *self.callback.lock().unwrap() = callback;
+    }
+}
+
+/// Polls the engine process until it exits or the SDK shuts down.
+/// `on_crash` runs on this thread when the engine exits while `expected_exit` is unset
+pub fn spawn_supervisor_thread(
+    engine_process: Arc<Mutex<Child>>,
+    shutdown: Arc<AtomicBool>,
+    expected_exit: Arc<AtomicBool>,
+    on_crash: Box<dyn FnOnce(String) + Send>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        while !shutdown.load(Ordering::Relaxed) {
+            let exit = engine_process.lock().unwrap().try_wait();
+
+            match exit {
+                Ok(Some(status)) => {
+                    if !expected_exit.load(Ordering::SeqCst) {
+                        on_crash(describe_exit(status));
+                    }
+                    return;
+                }
+                Ok(None) => thread::sleep(Duration::from_millis(100)),
+                Err(e) => {
+                    log::warn!("Failed to query engine process status: {}", e);
+                    return;
+                }
+            }
+        }
+    })
+}
+
+pub fn describe_exit(status: ExitStatus) -> String {
+    if let Some(code) = status.code() {
+        return format!("engine exited with code {}", code);
+    }
+    #[cfg(unix)]
+    if let Some(signal) = status.signal() {
+        return format!("engine killed by signal {}", signal);
+    }
+    format!("engine exited: {}", status)
+}
+
+/// Polls the engine until it exits or `deadline` passes, returning whether it exited
+pub fn wait_for_exit(engine_process: &Mutex<Child>, deadline: Instant) -> bool {
+    loop {
+        match engine_process.lock().unwrap().try_wait() {
+            Ok(Some(_)) => return true,
+            Ok(None) => {}
+            // The process can't be queried any more, treat it as gone
+            Err(_) => return true,
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+/// Waits for an engine that was asked to stop until `deadline`, then escalates SIGTERM → SIGKILL.
+/// Without signals (Windows) it goes straight to killing the process
+pub fn escalate_stop(engine_process: &Mutex<Child>, asked: bool, deadline: Instant) -> StopOutcome {
+    if asked && wait_for_exit(engine_process, deadline) {
+        return StopOutcome::Graceful;
+    }
+
+    #[cfg(unix)]
+    {
+        log::warn!("Engine did not exit in time, sending SIGTERM");
+        if !terminate(engine_process) {
+            // It exited between our last check and the signal
+            return StopOutcome::Graceful;
+        }
+        if wait_for_exit(engine_process, Instant::now() + TERM_GRACE) {
+            return StopOutcome::Terminated;
+        }
+        log::warn!("Engine ignored SIGTERM, killing it");
+    }
+    #[cfg(not(unix))]
+    log::warn!("Engine did not exit in time, killing it");
+
+    let mut child = engine_process.lock().unwrap();
+    let _ = child.kill();
+    let _ = child.wait();
+    StopOutcome::Killed
+}
+
+/// Sends SIGTERM unless the engine has already exited, returning whether it was sent.
+/// The child stays locked from the check to the signal, so its pid can't have been
+/// reaped (and reused) in between
+#[cfg(unix)]
+fn terminate(engine_process: &Mutex<Child>) -> bool {
+    let mut child = engine_process.lock().unwrap();
+    if !matches!(child.try_wait(), Ok(None)) {
+        return false;
+    }
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use std::process::Command;
+
+    #[cfg(unix)]
+    fn spawn(script: &str) -> Mutex<Child> {
+        Mutex::new(Command::new("sh").arg("-c").arg(script).spawn().unwrap())
+    }
+
+    fn policy(initial_ms: u64, max_ms: u64) -> RestartPolicy {
+        RestartPolicy {
+            max_restarts: 5,
+            initial_backoff: Duration::from_millis(initial_ms),
+            max_backoff: Duration::from_millis(max_ms),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn engine_that_exits_when_asked_is_graceful() {
+        let engine = spawn("exit 0");
+        let outcome = escalate_stop(&engine, true, Instant::now() + Duration::from_secs(5));
+        assert_eq!(outcome, StopOutcome::Graceful);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn engine_that_was_not_asked_gets_sigterm() {
+        let engine = spawn("exec sleep 30");
+        let outcome = escalate_stop(&engine, false, Instant::now());
+        assert_eq!(outcome, StopOutcome::Terminated);
+        assert!(engine.lock().unwrap().try_wait().unwrap().is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn engine_ignoring_sigterm_is_killed() {
+        let engine = spawn("trap '' TERM; exec sleep 30");
+        // Give the shell time to install its trap before the deadline passes
+        let outcome = escalate_stop(&engine, true, Instant::now() + Duration::from_millis(300));
+        assert_eq!(outcome, StopOutcome::Killed);
+        assert!(engine.lock().unwrap().try_wait().unwrap().is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn engine_exiting_before_the_signal_is_not_signalled() {
+        let engine = spawn("exit 3");
+        assert!(wait_for_exit(
+            &engine,
+            Instant::now() + Duration::from_secs(5)
+        ));
+        // Already reaped: terminate() must not signal a pid that may have been reused
+        assert!(!terminate(&engine));
+        assert_eq!(
+            escalate_stop(&engine, false, Instant::now()),
+            StopOutcome::Graceful
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_per_attempt() {
+        let policy = policy(100, 10_000);
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = policy(100, 1_000);
+        assert_eq!(policy.backoff(4), Duration::from_millis(1_000));
+        assert_eq!(policy.backoff(10), Duration::from_millis(1_000));
+    }
+
+    #[test]
+    fn late_attempts_do_not_overflow() {
+        let policy = policy(500, 30_000);
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(30_000));
+        let uncapped = RestartPolicy {
+            max_backoff: Duration::MAX,
+            ..policy
+        };
+        assert_eq!(uncapped.backoff(40), uncapped.backoff(16));
+    }
+
+    #[test]
+    fn disabled_policy_never_restarts() {
+        assert_eq!(RestartPolicy::disabled().max_restarts, 0);
+        assert_eq!(RestartPolicy::default().max_restarts, DEFAULT_MAX_RESTARTS);
+    }
+}
@@ -6,10 +6,15 @@
 //! Batches downsample and drop calls for efficiency.
 
 use pyo3::prelude::*;
+use std::sync::Arc;
 
+use pivot_com_types::EngineResponse;
 use pivot_com_types::fields::Uuid;
 
+use crate::engine::Engine;
 use crate::engine_api;
+use crate::engine_client::EngineClient;
+use crate::error::SdkError;
 
 /// Channel bit flags (must match engine constants)
 const CHANNEL_X: u32 = 1 << 0;
@@ -61,6 +66,7 @@ pub enum TboExportMode {
 /// when to trigger flush based on target file size.
 #[pyclass(unsendable)]
 pub struct TboExportContext {
+    client: Arc<EngineClient>,
     output_dir: String,
     target_bytes: u64,
     flags: u32,
@@ -77,9 +83,16 @@ pub struct TboExportContext {
 
 #[pymethods]
 impl TboExportContext {
+    /// Args:
+    ///     engine: Engine instance to export from (default: the module-level engine)
     #[new]
-    fn new() -> Self {
+    #[pyo3(signature = (engine=None))]
+    fn new(engine: Option<PyRef<'_, Engine>>) -> Self {
         Self {
+            client: match engine {
+                Some(engine) => engine.client.clone(),
+                None => engine_api::CLIENT.clone(),
+            },
             output_dir: String::new(),
             target_bytes: 0,
             flags: 0,
@@ -107,6 +120,7 @@ impl TboExportContext {
     #[pyo3(text_signature = "(self, output_dir, target_bytes, flags, target_point_count, batch_size, export_mode)")]
     fn init(
         &mut self,
+        py: Python,
         output_dir: String,
         target_bytes: u64,
         flags: u32,
@@ -146,8 +160,8 @@ impl TboExportContext {
             100_000
         };
 
-        eprintln!(
-            "[TBO] Config: target={} GB, channels={}, pts={}, mode={}, flush_threshold={}, batch_size={}",
+        log::info!(
+            "TBO export config: target={} GB, channels={}, pts={}, mode={}, flush_threshold={}, batch_size={}",
             target_bytes as f64 / (1024.0 * 1024.0 * 1024.0),
             channel_count,
             target_point_count,
@@ -162,8 +176,13 @@ impl TboExportContext {
 
         // Configure engine with compute params only (for points mode)
         if let TboExportMode::Points = &self.export_mode {
-            engine_api::tbo_config_command(self.channel_mask, self.target_point_count)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e))?;
+            py.detach(|| {
+                engine_api::tbo_config_command(
+                    &self.client,
+                    self.channel_mask,
+                    self.target_point_count,
+                )
+            })?;
         }
 
         Ok(())
@@ -179,7 +198,7 @@ impl TboExportContext {
     ///
     /// Returns:
     ///     Number of meshes accumulated in this call (1 if batch flushed, 0 if still pending)
-    fn accumulate(&mut self, uuid_bytes: Vec<u8>) -> PyResult<u32> {
+    fn accumulate(&mut self, py: Python, uuid_bytes: Vec<u8>) -> PyResult<u32> {
         if uuid_bytes.len() != Uuid::SIZE {
             return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
                 format!("UUID must be {} bytes, got {}", Uuid::SIZE, uuid_bytes.len()),
@@ -191,14 +210,14 @@ impl TboExportContext {
 
         // Check if batch is full - downsample immediately to avoid buffer overflow
         if self.pending_downsample.len() >= self.batch_size {
-            return self.flush_pending();
+            return self.flush_pending(py);
         }
 
         Ok(0)
     }
 
     /// Flush pending downsample and drop calls to the engine.
-    fn flush_pending(&mut self) -> PyResult<u32> {
+    fn flush_pending(&mut self, py: Python) -> PyResult<u32> {
         if self.pending_downsample.is_empty() {
             return Ok(0);
         }
@@ -226,31 +245,24 @@ impl TboExportContext {
         let pivot_downsample = pivot_downsample?;
         let count = pivot_downsample.len();
 
-        match engine_api::tbo_downsample_command(pivot_downsample) {
-            Ok(resp) => {
-                let accumulated = resp.read_tbo_downsample();
-                self.accumulated_count += accumulated as u64;
-
-                // Drop
-                let pivot_drop: Result<Vec<Uuid>, PyErr> = drop_uuids
-                    .iter()
-                    .map(|bytes| {
-                        let mut uuid = Uuid { bytes: [0u8; Uuid::SIZE] };
-               uuid.bytes.copy_from_slice(&bytes);
-                        Ok(uuid)
-                    })
-                    .collect();
+        let resp = py
+            .detach(|| engine_api::tbo_downsample_command(&self.client, pivot_downsample))
+            .map_err(|e| e.context(format!("tbo_downsample of {} meshes failed", count)))?;
+        let accumulated = resp.read_tbo_downsample();
+        self.accumulated_count += accumulated as u64;
 
-                let pivot_drop = pivot_drop.map_err(|e| e)?;
-                engine_api::drop_groups_command(pivot_drop)
-                    .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e))?;
+        // Drop
+        let pivot_drop: Vec<Uuid> = drop_uuids
+            .iter()
+            .map(|bytes| {
+                let mut uuid = Uuid { bytes: [0u8; Uuid::SIZE] };
+                uuid.bytes.copy_from_slice(bytes);
+                uuid
+            })
+            .collect();
+        py.detach(|| engine_api::drop_groups_command(&self.client, pivot_drop))?;
 
-                Ok(accumulated)
-            }
-            Err(e) => Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                format!("tbo_downsample failed: {}, requested: {}", e, count),
-            )),
-        }
+        Ok(accumulated)
     }
 
     /// Downsample a batch of UUIDs from the engine scene graph and accumulate results.
@@ -260,7 +272,7 @@ impl TboExportContext {
     ///
     /// Returns:
     ///     Number of meshes successfully accumulated
-    fn downsample(&mut self, uuids: Vec<Vec<u8>>) -> PyResult<u32> {
+    fn downsample(&mut self, py: Python, uuids: Vec<Vec<u8>>) -> PyResult<u32> {
         // For meshes/lbo mode, skip downsample (export does its own)
         if matches!(&self.export_mode, TboExportMode::Meshes | TboExportMode::Lbo) {
             return Ok(uuids.len() as u32);
@@ -283,23 +295,19 @@ impl TboExportContext {
         let pivot_uuids = pivot_uuids?;
         let count = pivot_uuids.len();
 
-        match engine_api::tbo_downsample_command(pivot_uuids) {
-            Ok(resp) => {
-                let accumulated = resp.read_tbo_downsample();
-                self.accumulated_count += accumulated as u64;
-                Ok(accumulated)
-            }
-            Err(e) => Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                format!("tbo_downsample failed: {}, requested: {}", e, count),
-            )),
-        }
+        let resp = py
+            .detach(|| engine_api::tbo_downsample_command(&self.client, pivot_uuids))
+            .map_err(|e| e.context(format!("tbo_downsample of {} meshes failed", count)))?;
+        let accumulated = resp.read_tbo_downsample();
+        self.accumulated_count += accumulated as u64;
+        Ok(accumulated)
     }
 
     /// Drop a batch of UUIDs from the engine scene graph and memory bank.
     ///
     /// Args:
     ///     uuids: List of UUID byte arrays (each 32 bytes)
-    fn drop(&self, uuids: Vec<Vec<u8>>) -> PyResult<()> {
+    fn drop(&self, py: Python, uuids: Vec<Vec<u8>>) -> PyResult<()> {
         // For meshes/lbo mode, skip drop (export does its own)
         if matches!(&self.export_mode, TboExportMode::Meshes | TboExportMode::Lbo) {
             return Ok(());
@@ -316,8 +324,7 @@ impl TboExportContext {
 
         let pivot_uuids = pivot_uuids.map_err(|e| e)?;
 
-        engine_api::drop_groups_command(pivot_uuids)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e))?;
+        py.detach(|| engine_api::drop_groups_command(&self.client, pivot_uuids))?;
 
         Ok(())
     }
@@ -326,68 +333,56 @@ impl TboExportContext {
     ///
     /// Returns:
     ///     List of written .tbo filenames
-    fn flush(&mut self) -> PyResult<Vec<String>> {
+    fn flush(&mut self, py: Python) -> PyResult<Vec<String>> {
         match &self.export_mode {
             TboExportMode::Points => {
-                self.flush_pending()?;
+                self.flush_pending(py)?;
                 let batch_offset = self.next_batch_number;
-                match engine_api::tbo_flush_command(&self.output_dir, self.target_bytes, batch_offset) {
-                    Ok(resp) => {
-                        let filenames = resp.read_tbo_flush()
-                            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                                format!("Failed to read flush response: {}", e),
-                            ))?;
-                        let result: Vec<String> = filenames.into_iter().map(|s| s.to_string()).collect();
-                        // Update batch offset for next flush
-                        self.next_batch_number += result.len() as u32;
-                        // Reset accumulated count so needs_flush works correctly for next batch
-                        self.accumulated_count = 0;
-                        // Drop all groups from scene graph to clear memory
-                        engine_api::drop_all_groups_command()
-                            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                                format!("drop_all_groups failed: {}", e),
-                            ))?;
-                        Ok(result)
-                    }
-                    Err(e) => Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                        format!("tbo_flush failed: {}", e),
-                    )),
-                }
+                let resp = py
+                    .detach(|| {
+                        engine_api::tbo_flush_command(
+                            &self.client,
+                            &self.output_dir,
+                            self.target_bytes,
+                            batch_offset,
+                        )
+                    })
+                    .map_err(|e| e.context("tbo_flush failed"))?;
+                let result = read_filenames(&resp)?;
+                // Update batch offset for next flush
+                self.next_batch_number += result.len() as u32;
+                // Reset accumulated count so needs_flush works correctly for next batch
+                self.accumulated_count = 0;
+                // Drop all groups from scene graph to clear memory
+                self.drop_all_groups(py)?;
+                Ok(result)
             }
             TboExportMode::Meshes => {
-                match engine_api::export_all_asset_tbo_command(&self.output_dir, self.target_bytes) {
-                    Ok(resp) => {
-                        let filenames = resp.read_tbo_flush()
-                            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                                format!("Failed to read flush response: {}", e),
-                            ))?;
-                        let result: Vec<String> = filenames.into_iter().map(|s| s.to_string()).collect();
-                        self.accumulated_count = 0;
-                        // Drop all groups from scene graph to clear memory
-                        engine_api::drop_all_groups_command()
-                            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                                format!("drop_all_groups failed: {}", e),
-                            ))?;
-                        Ok(result)
-                    }
-                    Err(e) => Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                        format!("export_all_asset_tbo failed: {}", e),
-                    )),
-                }
+                let resp = py
+                    .detach(|| {
+                        engine_api::export_all_asset_tbo_command(
+                            &self.client,
+                            &self.output_dir,
+                            self.target_bytes,
+                        )
+                    })
+                    .map_err(|e| e.context("export_all_asset_tbo failed"))?;
+                let result = read_filenames(&resp)?;
+                self.accumulated_count = 0;
+                // Drop all groups from scene graph to clear memory
+                self.drop_all_groups(py)?;
+                Ok(result)
             }
             TboExportMode::Lbo => {
                 // Export all assets to LBO format
-                engine_api::export_all_command(&self.output_dir, self.target_bytes)
-                    .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                        format!("export_all failed: {}", e),
-                    ))?;
-                
+                py.detach(|| {
+                    engine_api::export_all_command(&self.client, &self.output_dir, self.target_bytes)
+                })
+                .map_err(|e| e.context("export_all failed"))?;
+
                 // Drop all groups from scene graph
-                engine_api::drop_all_groups_command()
-                    .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                        format!("drop_all_groups failed: {}", e),
-                    ))?;
-                
+                self.drop_all_groups(py)?;
+
                 self.accumulated_count = 0;
                 Ok(vec![])
             }
@@ -398,33 +393,31 @@ impl TboExportContext {
     ///
     /// Returns:
     ///     Total number of meshes accumulated during this export session
-    fn finalize(&mut self) -> PyResult<u64> {
+    fn finalize(&mut self, py: Python) -> PyResult<u64> {
         // Flush any pending downsample/drop calls
         if !self.pending_downsample.is_empty() {
-            self.flush_pending()?;
+            self.flush_pending(py)?;
         }
 
         // Flush to disk
-        let files = self.flush()?;
+        let files = self.flush(py)?;
         
         match &self.export_mode {
             TboExportMode::Points => {
-                eprintln!(
-                    "[TBO] Final flush: {} files, total meshes: {}",
+                log::info!(
+                    "TBO final flush: {} files, total meshes: {}",
                     files.len(),
                     self.accumulated_count,
                 );
             }
             TboExportMode::Meshes => {
-                eprintln!(
-                    "[TBO] Final flush: {} files, total assets exported",
+                log::info!(
+                    "TBO final flush: {} files, total assets exported",
                     files.len(),
                 );
             }
             TboExportMode::Lbo => {
-                eprintln!(
-                    "[LBO] Final flush: exported all assets"
-                );
+                log::info!("LBO final flush: exported all assets");
             }
         }
         
@@ -455,3 +448,20 @@ impl TboExportContext {
         self.pending_downsample.len()
     }
 }
+
+impl TboExportContext {
+    /// Drops every group from the engine's scene graph to free its memory after a flush
+    fn drop_all_groups(&self, py: Python) -> Result<(), SdkError> {
+        py.detach(|| engine_api::drop_all_groups_command(&self.client))
+            .map_err(|e| e.context("drop_all_groups failed"))?;
+        Ok(())
+    }
+}
+
+/// The .tbo files a flush or export reports having written
+fn read_filenames(resp: &EngineResponse) -> Result<Vec<String>, SdkError> {
+    let filenames = resp
+        .read_tbo_flush()
+        .map_err(|e| SdkError::Decode(format!("Failed to read flush response: {}", e)))?;
+    Ok(filenames.into_iter().map(|s| s.to_string()).collect())
+}